@@ -0,0 +1,153 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::board::DIRECTIONS;
+use crate::board::Direction;
+use crate::board::EmptyBoard;
+use crate::board::Position;
+
+// Which tiles of a map robots can use. A tile is reachable if it's in the
+// largest region that robots can step around in, so the walled-in centre of
+// the standard boards (or any other sealed-off pocket) is not. A reachable
+// tile is a natural stop if some slide ends on it against a wall; robots
+// can only stop on the other reachable tiles when something blocks them.
+// Targets of the board game sit in corners, reachable tiles off the edge of
+// the board with walls on two sides that meet.
+pub struct MapAnalysis {
+    reachable: [[bool; 16]; 16],
+    natural_stop: [[bool; 16]; 16],
+    corner: [[bool; 16]; 16],
+}
+
+impl MapAnalysis {
+    pub fn new(board: &EmptyBoard) -> MapAnalysis {
+        let mut reachable = [[false; 16]; 16];
+        let mut best_size = 0;
+        for y in 0..16 {
+            for x in 0..16 {
+                let region = region_from(board, Position { x, y });
+                let size = region.iter().flatten().filter(|tile| **tile).count();
+                if size > best_size {
+                    best_size = size;
+                    reachable = region;
+                }
+            }
+        }
+
+        let mut natural_stop = [[false; 16]; 16];
+        let mut corner = [[false; 16]; 16];
+        for y in 0..16 {
+            for x in 0..16 {
+                let position = Position { x, y };
+                if !reachable[x as usize][y as usize] {
+                    continue;
+                }
+                let on_edge = x == 0 || x == 15 || y == 0 || y == 15;
+                let walled_vertically = !board.can_move(position, Direction::UP) || !board.can_move(position, Direction::DOWN);
+                let walled_horizontally = !board.can_move(position, Direction::LEFT) || !board.can_move(position, Direction::RIGHT);
+                corner[x as usize][y as usize] = !on_edge && walled_vertically && walled_horizontally;
+                let tile = board.get_tile(position);
+                natural_stop[x as usize][y as usize] = DIRECTIONS.iter().any(|direction| {
+                    if board.can_move(position, *direction) || !board.can_move(position, direction.opposite()) {
+                        return false;
+                    }
+                    let from = tile.get_adjacent_position(direction.opposite());
+                    reachable[from.x as usize][from.y as usize] && board.can_move(from, *direction)
+                });
+            }
+        }
+
+        MapAnalysis {
+            reachable,
+            natural_stop,
+            corner,
+        }
+    }
+
+    pub fn is_reachable(&self, position: Position) -> bool {
+        in_bounds(position) && self.reachable[position.x as usize][position.y as usize]
+    }
+
+    pub fn is_natural_stop(&self, position: Position) -> bool {
+        in_bounds(position) && self.natural_stop[position.x as usize][position.y as usize]
+    }
+
+    pub fn needs_blocker(&self, position: Position) -> bool {
+        self.is_reachable(position) && !self.is_natural_stop(position)
+    }
+
+    // Reachable tiles, row by row.
+    pub fn reachable_tiles(&self) -> Vec<Position> {
+        self.tiles_where(|position| self.is_reachable(position))
+    }
+
+    pub fn unreachable_tiles(&self) -> Vec<Position> {
+        self.tiles_where(|position| !self.is_reachable(position))
+    }
+
+    pub fn blocker_tiles(&self) -> Vec<Position> {
+        self.tiles_where(|position| self.needs_blocker(position))
+    }
+
+    // Tiles where the board game could put a target.
+    pub fn corner_tiles(&self) -> Vec<Position> {
+        self.tiles_where(|position| in_bounds(position) && self.corner[position.x as usize][position.y as usize])
+    }
+
+    pub fn validate(&self, robots: &[Position], goal: Position) -> Result<(), String> {
+        for (i, robot) in robots.iter().enumerate() {
+            if !self.is_reachable(*robot) {
+                return Err(format!("robot {} at {} is on a tile robots can't reach", i, robot));
+            }
+            if robots[..i].contains(robot) {
+                return Err(format!("more than one robot at {}", robot));
+            }
+        }
+        if !self.is_reachable(goal) {
+            return Err(format!("goal {} is on a tile robots can't reach", goal));
+        }
+        Ok(())
+    }
+
+    // `robot_count` distinct reachable tiles, uniformly at random.
+    pub fn random_placement<R: Rng>(&self, rng: &mut R, robot_count: usize) -> Vec<Position> {
+        self.reachable_tiles().choose_multiple(rng, robot_count).cloned().collect()
+    }
+
+    fn tiles_where<F: Fn(Position) -> bool>(&self, predicate: F) -> Vec<Position> {
+        let mut tiles = Vec::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                let position = Position { x, y };
+                if predicate(position) {
+                    tiles.push(position);
+                }
+            }
+        }
+        tiles
+    }
+}
+
+fn in_bounds(position: Position) -> bool {
+    position.x >= 0 && position.x < 16 && position.y >= 0 && position.y < 16
+}
+
+// Every tile a robot starting on `start` can step onto, one tile at a time.
+fn region_from(board: &EmptyBoard, start: Position) -> [[bool; 16]; 16] {
+    let mut region = [[false; 16]; 16];
+    region[start.x as usize][start.y as usize] = true;
+    let mut stack = vec![start];
+    while let Some(position) = stack.pop() {
+        for direction in DIRECTIONS.iter() {
+            if !board.can_move(position, *direction) {
+                continue;
+            }
+            let next = board.get_tile(position).get_adjacent_position(*direction);
+            if !region[next.x as usize][next.y as usize] {
+                region[next.x as usize][next.y as usize] = true;
+                stack.push(next);
+            }
+        }
+    }
+    region
+}
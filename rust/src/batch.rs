@@ -0,0 +1,277 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use hashbrown::HashMap;
+use hashbrown::HashSet;
+
+use crate::bitboard::Walls;
+use crate::board::Board;
+use crate::board::COLOURS;
+use crate::board::Colour;
+use crate::board::Position;
+use crate::bucket::BucketQueue;
+use crate::colour_to_int;
+use crate::constraints::Constraints;
+use crate::goal::GoalSpec;
+use crate::heuristic::Heuristic;
+use crate::pruning::Arrival;
+use crate::rules::Rules;
+use crate::solver::AStar;
+use crate::solver::Limits;
+use crate::solver::ROOT;
+use crate::solver::Solution;
+use crate::solver::SolveStats;
+use crate::solver::Solver;
+use crate::solver::StopReason;
+use crate::solver::Trail;
+use crate::solver::Watch;
+use crate::solver::neighbours;
+use crate::state::State;
+
+// The shortest way to get one robot onto one target.
+pub struct TargetEntry {
+    pub target: Position,
+    pub colour: Colour,
+    pub solution: Option<Solution>,
+    // Set if there was no solution within the limits, rather than none at all.
+    pub stopped: Option<StopReason>,
+}
+
+// Every target and colour from one placement of the robots.
+pub struct TargetTable {
+    // Target by target, each in colour order.
+    pub entries: Vec<TargetEntry>,
+    // How many entries the shared sweep settled, the rest took a search each.
+    pub from_sweep: usize,
+    pub stats: SolveStats,
+}
+
+// A board a search has reached: where the robots are, how far it is, the
+// path there and the move that got there.
+type Node = (State, i16, usize, Option<Arrival>);
+
+// What the breadth first sweep leaves for the searches after it.
+struct Sweep {
+    trail: Trail,
+    // Every board the sweep reached, each at its exact distance.
+    reached: HashSet<u64>,
+    // Boards reached but not expanded, which every path to the rest of the
+    // boards goes through.
+    frontier: Vec<Node>,
+    // Whether it reached every board there is.
+    exhausted: bool,
+}
+
+// Solves every target for every colour from the same robots. One breadth
+// first sweep from the robots as they stand serves them all: the first board
+// it reaches with a robot on a target is the shortest way to get it there.
+// The sweep stops after `sweep_nodes` boards, and each target it hasn't
+// settled for every colour gets an A* search that carries on from it: it
+// starts from the sweep's frontier at the distances the sweep found, takes
+// the boards the sweep reached as closed, and looks for every colour still
+// open at once, with the lowest estimate of theirs.
+//
+// The sweep works on exact boards, since every colour is a target for
+// something, and leaves out rules and constraints, which depend on the
+// target. Those get an A* search of their own for each target and colour.
+pub struct BatchSolver {
+    sweep_nodes: u64,
+}
+
+impl BatchSolver {
+    pub fn new(sweep_nodes: u64) -> BatchSolver {
+        BatchSolver { sweep_nodes }
+    }
+
+    pub fn solve(
+        &self,
+        board: &Board,
+        targets: &[Position],
+        limits: &Limits,
+        heuristic_for: &mut dyn FnMut(Position) -> Box<dyn Heuristic>,
+    ) -> TargetTable {
+        let start_time = Instant::now();
+        let mut entries: Vec<TargetEntry> = targets.iter().flat_map(|target| {
+            COLOURS.iter().map(move |colour| TargetEntry { target: *target, colour: *colour, solution: None, stopped: None })
+        }).collect();
+        let mut stats = SolveStats::default();
+        if limits.rules.any() || limits.constraints.any() {
+            solve_separately(board, targets, limits, heuristic_for, &mut entries, &mut stats);
+            stats.wall_time = start_time.elapsed();
+            return TargetTable { entries, from_sweep: 0, stats };
+        }
+
+        let walls = Walls::new(&board.empty_board());
+        let mut sweep = self.sweep(board, &walls, targets, limits, &mut entries, &mut stats);
+        let from_sweep = entries.iter().filter(|entry| entry.solution.is_some()).count();
+        // Nothing the sweep left has a solution if it ran out of boards.
+        if !sweep.exhausted {
+            for (index, target) in targets.iter().enumerate() {
+                let pending = &mut entries[index * COLOURS.len()..(index + 1) * COLOURS.len()];
+                if pending.iter().all(|entry| entry.solution.is_some()) {
+                    continue;
+                }
+                let heuristic = heuristic_for(*target);
+                let search_stats = carry_on(&walls, &mut sweep, *target, &*heuristic, limits, pending);
+                stats.add(&search_stats);
+            }
+        }
+        stats.wall_time = start_time.elapsed();
+        TargetTable { entries, from_sweep, stats }
+    }
+
+    // Fills in the entries a breadth first sweep reaches.
+    fn sweep(&self, board: &Board, walls: &Walls, targets: &[Position], limits: &Limits, entries: &mut [TargetEntry], stats: &mut SolveStats) -> Sweep {
+        let mut target_index = [[None; 16]; 16];
+        for (index, target) in targets.iter().enumerate() {
+            target_index[target.x as usize][target.y as usize] = Some(index);
+        }
+        let sweep_limits = Limits {
+            max_nodes: Some(limits.max_nodes.map_or(self.sweep_nodes, |nodes| nodes.min(self.sweep_nodes))),
+            max_depth: None,
+            rules: Rules::default(),
+            constraints: Constraints::default(),
+            ..limits.clone()
+        };
+        let any_goal = GoalSpec::Robot(Colour::RED);
+        let mut watch = Watch::new(&sweep_limits);
+        let mut trail = Trail::new();
+        let mut queue: VecDeque<Node> = VecDeque::new();
+        let mut reached = HashSet::new();
+        let mut unsettled = entries.len();
+        let start = board.state();
+        reached.insert(start.key());
+        queue.push_back((start, 0, ROOT, None));
+        unsettled -= settle(start, ROOT, &trail, &target_index, entries);
+
+        while let Some(node) = queue.front().copied() {
+            let (current, distance, path, arrival) = node;
+            if unsettled == 0 || watch.check(stats, distance).is_some() {
+                return Sweep { trail, reached, frontier: queue.into_iter().collect(), exhausted: false };
+            }
+            queue.pop_front();
+            stats.nodes_expanded += 1;
+            for (step, neighbour) in neighbours(walls, current, arrival, &sweep_limits, &any_goal, stats) {
+                stats.nodes_generated += 1;
+                if !reached.insert(neighbour.key()) {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                let neighbour_path = trail.push(path, step);
+                unsettled -= settle(neighbour, neighbour_path, &trail, &target_index, entries);
+                let from = current.robot(step.colour);
+                queue.push_back((neighbour, distance + 1, neighbour_path, Some(Arrival { step, from })));
+                stats.peak_open = stats.peak_open.max(queue.len() as u64);
+            }
+        }
+        Sweep { trail, reached, frontier: Vec::new(), exhausted: true }
+    }
+}
+
+// A* onto `target` for the colours of `pending` not yet settled, carrying on
+// from where the sweep stopped. Every path to a board the sweep didn't reach
+// goes through its frontier, and the sweep reached its own boards by
+// shortest paths, so nothing shorter is left out.
+fn carry_on(walls: &Walls, sweep: &mut Sweep, target: Position, heuristic: &dyn Heuristic, limits: &Limits, pending: &mut [TargetEntry]) -> SolveStats {
+    let mut stats = SolveStats::default();
+    let mut watch = Watch::new(limits);
+    let any_goal = GoalSpec::Robot(Colour::RED);
+    let mut open_colours: Vec<Colour> = pending.iter().filter(|entry| entry.solution.is_none()).map(|entry| entry.colour).collect();
+    // The lowest estimate over the colours still open. Settling one only
+    // raises it, so what's queued stays a lower bound.
+    let estimate = |state: State, open_colours: &[Colour], stats: &mut SolveStats| {
+        stats.heuristic_calls += open_colours.len() as u64;
+        open_colours.iter().map(|colour| heuristic.evaluate(state, *colour)).min().unwrap_or(0)
+    };
+    let mut best: HashMap<u64, i16> = HashMap::new();
+    let mut open = BucketQueue::new();
+    for node in &sweep.frontier {
+        open.push((node.1 + estimate(node.0, &open_colours, &mut stats)) as usize, *node);
+    }
+    let mut depth_cut = false;
+    while let Some((priority, (state, distance, path, arrival))) = open.pop() {
+        // A better way here was found after this one was queued.
+        if best.get(&state.key()).is_some_and(|shortest| *shortest < distance) {
+            stats.duplicates_skipped += 1;
+            continue;
+        }
+        for entry in pending.iter_mut().filter(|entry| entry.solution.is_none() && state.robot(entry.colour) == target) {
+            entry.solution = Some(Solution { moves: sweep.trail.moves(path) });
+            open_colours.retain(|colour| colour_to_int(*colour) != colour_to_int(entry.colour));
+        }
+        if open_colours.is_empty() {
+            return stats;
+        }
+        if let Some(stopped) = watch.check(&stats, priority as i16) {
+            mark_stopped(pending, Some(stopped));
+            return stats;
+        }
+        stats.nodes_expanded += 1;
+        if !limits.allows_depth(distance + 1) {
+            depth_cut = true;
+            continue;
+        }
+        for (step, neighbour) in neighbours(walls, state, arrival, limits, &any_goal, &mut stats) {
+            stats.nodes_generated += 1;
+            let key = neighbour.key();
+            if sweep.reached.contains(&key) || best.get(&key).is_some_and(|shortest| *shortest <= distance + 1) {
+                stats.duplicates_skipped += 1;
+                continue;
+            }
+            best.insert(key, distance + 1);
+            let neighbour_path = sweep.trail.push(path, step);
+            let from = state.robot(step.colour);
+            let priority = (distance + 1 + estimate(neighbour, &open_colours, &mut stats)) as usize;
+            open.push(priority, (neighbour, distance + 1, neighbour_path, Some(Arrival { step, from })));
+            stats.peak_open = stats.peak_open.max(open.len() as u64);
+        }
+    }
+    mark_stopped(pending, if depth_cut { Some(StopReason::DepthLimit) } else { None });
+    stats
+}
+
+fn mark_stopped(pending: &mut [TargetEntry], stopped: Option<StopReason>) {
+    for entry in pending.iter_mut().filter(|entry| entry.solution.is_none()) {
+        entry.stopped = stopped;
+    }
+}
+
+// An A* search for each target and colour, for rules and constraints the
+// sweep can't take.
+fn solve_separately(
+    board: &Board,
+    targets: &[Position],
+    limits: &Limits,
+    heuristic_for: &mut dyn FnMut(Position) -> Box<dyn Heuristic>,
+    entries: &mut [TargetEntry],
+    stats: &mut SolveStats,
+) {
+    for (index, target) in targets.iter().enumerate() {
+        let heuristic = heuristic_for(*target);
+        let solver = AStar::new(&*heuristic);
+        let target_board = board.with_goal(*target);
+        for entry in &mut entries[index * COLOURS.len()..(index + 1) * COLOURS.len()] {
+            let result = solver.solve(&target_board, &GoalSpec::Robot(entry.colour), limits);
+            stats.add(&result.stats);
+            entry.solution = result.solution;
+            entry.stopped = result.stopped;
+        }
+    }
+}
+
+// Records the path to `state` for every robot on a target that hasn't been
+// reached before, and how many that was.
+fn settle(state: State, path: usize, trail: &Trail, target_index: &[[Option<usize>; 16]; 16], entries: &mut [TargetEntry]) -> usize {
+    let mut settled = 0;
+    for colour in COLOURS.iter() {
+        let position = state.robot(*colour);
+        if let Some(index) = target_index[position.x as usize][position.y as usize] {
+            let entry = &mut entries[index * COLOURS.len() + colour_to_int(*colour) as usize];
+            if entry.solution.is_none() {
+                entry.solution = Some(Solution { moves: trail.moves(path) });
+                settled += 1;
+            }
+        }
+    }
+    settled
+}
@@ -0,0 +1,152 @@
+use crate::board::Direction;
+use crate::board::EmptyBoard;
+use crate::board::Position;
+
+// Where slides stop because of walls, as one bit per tile. Rows are indexed
+// by y with bit x for each tile, columns by x with bit y, so a slide along a
+// row or column is a bit scan from the robot's bit towards the wall.
+pub struct Walls {
+    // Set where a robot can't move any further in that direction.
+    stop_up: [u16; 16],
+    stop_down: [u16; 16],
+    stop_left: [u16; 16],
+    stop_right: [u16; 16],
+}
+
+impl Walls {
+    pub fn new(board: &EmptyBoard) -> Walls {
+        let mut walls = Walls {
+            stop_up: [0; 16],
+            stop_down: [0; 16],
+            stop_left: [0; 16],
+            stop_right: [0; 16],
+        };
+        for x in 0..16 {
+            for y in 0..16 {
+                let position = Position { x: x as i8, y: y as i8 };
+                if !board.can_move(position, Direction::UP) {
+                    walls.stop_up[x] |= 1 << y;
+                }
+                if !board.can_move(position, Direction::DOWN) {
+                    walls.stop_down[x] |= 1 << y;
+                }
+                if !board.can_move(position, Direction::LEFT) {
+                    walls.stop_left[y] |= 1 << x;
+                }
+                if !board.can_move(position, Direction::RIGHT) {
+                    walls.stop_right[y] |= 1 << x;
+                }
+            }
+        }
+        walls
+    }
+
+    // Where a robot at `from` stops sliding in `direction`, with the robots
+    // of `occupancy` in the way. `from` itself if it can't move.
+    pub fn slide(&self, occupancy: &Occupancy, from: Position, direction: Direction) -> Position {
+        let x = from.x as usize;
+        let y = from.y as usize;
+        match direction {
+            // A robot on the next tile stops the slide as a wall would, so
+            // shift the robots back one tile onto the wall masks.
+            Direction::UP => Position { x: from.x, y: highest_up_to(self.stop_up[x] | occupancy.columns[x] << 1, y) },
+            Direction::DOWN => Position { x: from.x, y: lowest_from(self.stop_down[x] | occupancy.columns[x] >> 1, y) },
+            Direction::LEFT => Position { x: highest_up_to(self.stop_left[y] | occupancy.rows[y] << 1, x), y: from.y },
+            Direction::RIGHT => Position { x: lowest_from(self.stop_right[y] | occupancy.rows[y] >> 1, x), y: from.y },
+        }
+    }
+}
+
+// Which tiles have a robot on them, by row and by column.
+#[derive(Copy, Clone)]
+pub struct Occupancy {
+    rows: [u16; 16],
+    columns: [u16; 16],
+}
+
+impl Occupancy {
+    pub fn new(robots: &[Position]) -> Occupancy {
+        let mut occupancy = Occupancy {
+            rows: [0; 16],
+            columns: [0; 16],
+        };
+        for robot in robots {
+            occupancy.rows[robot.y as usize] |= 1 << robot.x;
+            occupancy.columns[robot.x as usize] |= 1 << robot.y;
+        }
+        occupancy
+    }
+}
+
+// The walls always stop a slide at the edge of the board, so both scans
+// find a bit.
+fn lowest_from(stops: u16, from: usize) -> i8 {
+    (stops & (u16::MAX << from)).trailing_zeros() as i8
+}
+
+fn highest_up_to(stops: u16, from: usize) -> i8 {
+    15 - (stops & (u16::MAX >> (15 - from))).leading_zeros() as i8
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+
+    use super::*;
+    use crate::board::Board;
+    use crate::board::COLOURS;
+    use crate::board::DIRECTIONS;
+    use crate::board::load_map;
+
+    // Slides one tile at a time, the way moves worked before bitboards.
+    fn step_slide(board: &EmptyBoard, robots: &[Position], from: Position, direction: Direction) -> Position {
+        let mut position = from;
+        while board.can_move(position, direction) {
+            let next = board.get_tile(position).get_adjacent_position(direction);
+            if robots.contains(&next) {
+                break;
+            }
+            position = next;
+        }
+        position
+    }
+
+    // Every slide from random placements, some of them on tiles robots
+    // can't reach, ends where `Board::move_robot` and stepping put it.
+    #[test]
+    fn slides_match_stepping() {
+        let goal = Position { x: 6, y: 14 };
+        let map = Rc::new(load_map("maps/map1.txt"));
+        let empty_board = EmptyBoard::with_map(*map, goal);
+        let walls = Walls::new(&empty_board);
+        let tiles: Vec<Position> = (0..16).flat_map(|x| (0..16).map(move |y| Position { x, y })).collect();
+        let mut rng = StdRng::seed_from_u64(20);
+        for _ in 0..10000 {
+            let robots: Vec<Position> = tiles.choose_multiple(&mut rng, 4).cloned().collect();
+            let board = Board::with_map(map.clone(), robots[0], robots[1], robots[2], robots[3], goal);
+            let occupancy = Occupancy::new(&robots);
+            for colour in COLOURS.iter() {
+                for direction in DIRECTIONS.iter() {
+                    let expected = board.clone().move_robot(*colour, *direction);
+                    let actual = walls.slide(&occupancy, board.get_robot_by_colour(*colour), *direction);
+                    assert_eq!(actual, expected, "{:?} {:?} from {:?}", colour, direction, robots);
+                }
+            }
+
+            let robot_count = rng.gen_range(1, 9);
+            let robots: Vec<Position> = tiles.choose_multiple(&mut rng, robot_count).cloned().collect();
+            let occupancy = Occupancy::new(&robots);
+            for robot in &robots {
+                for direction in DIRECTIONS.iter() {
+                    let expected = step_slide(&empty_board, &robots, *robot, *direction);
+                    assert_eq!(walls.slide(&occupancy, *robot, *direction), expected, "{:?} from {} among {:?}", direction, robot, robots);
+                }
+            }
+        }
+    }
+}
@@ -2,7 +2,9 @@ use std::fmt;
 use std::fs;
 use std::rc::Rc;
 
-use thincollections::thin_vec::ThinVec;
+use crate::state::State;
+
+pub const DEFAULT_MAP: &str = "maps/map1.txt";
 
 const SIXTEEN_POWER_1: u64 = 16;
 const SIXTEEN_POWER_2: u64 = 256;
@@ -36,6 +38,19 @@ pub enum Direction {
     RIGHT,
 }
 
+pub const DIRECTIONS: [Direction; 4] = [Direction::UP, Direction::RIGHT, Direction::DOWN, Direction::LEFT];
+
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::UP => Direction::DOWN,
+            Direction::DOWN => Direction::UP,
+            Direction::LEFT => Direction::RIGHT,
+            Direction::RIGHT => Direction::LEFT,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Colour {
     RED,
@@ -44,6 +59,8 @@ pub enum Colour {
     YELLOW,
 }
 
+pub const COLOURS: [Colour; 4] = [Colour::RED, Colour::GREEN, Colour::BLUE, Colour::YELLOW];
+
 #[derive(Debug, Copy, Clone)]
 pub struct Tile {
     position: Position,
@@ -76,6 +93,8 @@ impl Tile {
     }
 }
 
+pub type Map = [[Tile; 16]; 16];
+
 #[derive(Debug, Clone)]
 pub struct Board {
     map: Rc<[[Tile; 16]; 16]>,
@@ -89,14 +108,14 @@ pub struct Board {
 impl Board {
 
     // public
-    pub fn new(
+    pub fn with_map(
+        map: Rc<Map>,
         red: Position,
         green: Position,
         blue: Position,
         yellow: Position,
         goal: Position,
     ) -> Board {
-        let map = Rc::new(load_map());
         let new_board = Board {
             red: red,
             green: green,
@@ -112,26 +131,6 @@ impl Board {
         return self.goal
     }
 
-    pub fn get_valid_directions(&self, colour: Colour) -> ThinVec<Direction> {
-        let robot_position = self.get_robot_by_colour(colour);
-        let tile = self.get_tile(robot_position);
-
-        let mut directions = ThinVec::new();
-        if tile.up && !self.is_occupied(tile.get_adjacent_position(Direction::UP)) {
-            directions.push(Direction::UP);
-        }
-        if tile.down && !self.is_occupied(tile.get_adjacent_position(Direction::DOWN)) {
-            directions.push(Direction::DOWN);
-        }
-        if tile.left && !self.is_occupied(tile.get_adjacent_position(Direction::LEFT)) {
-            directions.push(Direction::LEFT);
-        }
-        if tile.right && !self.is_occupied(tile.get_adjacent_position(Direction::RIGHT)) {
-            directions.push(Direction::RIGHT);
-        }
-        return directions
-    }
-
     pub fn get_robot_by_colour(&self, colour: Colour) -> Position {
         match colour {
             Colour::RED => self.red,
@@ -254,15 +253,6 @@ impl Board {
         return end_position;
     }
 
-    pub fn is_solved(&self, colour: Colour) -> bool {
-        return match colour {
-            Colour::RED => self.red == self.goal,
-            Colour::BLUE => self.blue == self.goal,
-            Colour::YELLOW => self.yellow == self.goal,
-            Colour::GREEN => self.green == self.goal,
-        }
-    }
-
     pub fn hash(&self) -> u64 {
         return self.red.x as u64 +
             SIXTEEN_POWER_1 * self.red.y as u64 +
@@ -275,6 +265,20 @@ impl Board {
 
     }
 
+    // The robots packed for the search, with the same value as `hash`.
+    pub fn state(&self) -> State {
+        State::new(self.red, self.green, self.blue, self.yellow)
+    }
+
+    // The same robots on the same map, aiming for another goal.
+    pub fn with_goal(&self, goal: Position) -> Board {
+        Board { goal, ..self.clone() }
+    }
+
+    pub fn empty_board(&self) -> EmptyBoard {
+        EmptyBoard::with_map(*self.map, self.goal)
+    }
+
     pub fn permuted_hashes(&self) -> [u64; 6] {
         return [self.red.x as u64 +
             (SIXTEEN_POWER_1 * self.red.y as u64) +
@@ -344,21 +348,6 @@ impl Board {
             Colour::YELLOW => self.yellow = position,
         }
     }
-
-    fn is_occupied(&self, position: Position) -> bool {
-        return self.red == position || self.blue == position || self.yellow == position || self.green == position
-    }
-
-    fn can_move(&self, position: Position, direction: Direction) -> bool {
-        let tile = self.get_tile(position);
-        let is_blocked_by_wall = match direction {
-            Direction::UP => !tile.up,
-            Direction::DOWN => !tile.down,
-            Direction::LEFT => !tile.left,
-            Direction::RIGHT => !tile.right,
-        };
-        return !is_blocked_by_wall && !self.is_occupied(tile.get_adjacent_position(direction))
-    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -370,10 +359,10 @@ pub struct EmptyBoard {
 impl EmptyBoard {
 
     // public
-    pub fn new(
+    pub fn with_map(
+        map: Map,
         goal: Position,
     ) -> EmptyBoard {
-        let map = load_map();
         let new_board = EmptyBoard {
             goal: goal,
             map: map,
@@ -381,29 +370,14 @@ impl EmptyBoard {
         return new_board
     }
 
-    pub fn get_valid_directions(&self, position: Position) -> Vec<Direction> {
-        let tile = self.get_tile(position);
-
-        let mut directions = Vec::new();
-        if tile.up {
-            directions.push(Direction::UP);
-        }
-        if tile.down {
-            directions.push(Direction::DOWN);
-        }
-        if tile.left {
-            directions.push(Direction::LEFT);
-        }
-        if tile.right {
-            directions.push(Direction::RIGHT);
-        }
-        return directions
-    }
-
     pub fn get_tile(&self, position: Position) -> Tile {
         return self.map[position.x as usize][position.y as usize]
     }
 
+    pub fn map_hash(&self) -> u64 {
+        hash_map(&self.map)
+    }
+
     pub fn can_move(&self, position: Position, direction: Direction) -> bool {
         let tile = self.get_tile(position);
         return !match direction {
@@ -433,9 +407,23 @@ fn can_move(tile: Tile, direction: Direction) -> bool {
     };
 }
 
-fn load_map() -> [[Tile; 16]; 16] {
+// FNV-1a over the walls of every tile, so files that only differ in
+// formatting still identify the same map.
+pub fn hash_map(map: &[[Tile; 16]; 16]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for column in map.iter() {
+        for tile in column.iter() {
+            let walls = tile.up as u64 | (tile.down as u64) << 1 | (tile.left as u64) << 2 | (tile.right as u64) << 3;
+            hash ^= walls;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+pub fn load_map(path: &str) -> Map {
     let mut map = init_map_array();
-    let contents = fs::read_to_string("maps/map1.txt").expect("Error reading map");
+    let contents = fs::read_to_string(path).expect("Error reading map");
     let lines: Vec<&str> = contents.split("\n").collect();
     let mut j = 0;
     for line in lines {
@@ -0,0 +1,45 @@
+// A priority queue for small non-negative integer priorities, one stack per
+// priority. Pops the lowest priority first and, within a priority, the entry
+// pushed last, so A* with equal f-values carries on down the path it was
+// already following.
+pub struct BucketQueue<T> {
+    buckets: Vec<Vec<T>>,
+    lowest: usize,
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    pub fn new() -> BucketQueue<T> {
+        BucketQueue {
+            buckets: Vec::new(),
+            lowest: 0,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, priority: usize, item: T) {
+        if priority >= self.buckets.len() {
+            self.buckets.resize_with(priority + 1, Vec::new);
+        }
+        self.buckets[priority].push(item);
+        // Heuristics that aren't consistent can push below the lowest
+        // priority popped so far.
+        self.lowest = self.lowest.min(priority);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<(usize, T)> {
+        while self.lowest < self.buckets.len() {
+            if let Some(item) = self.buckets[self.lowest].pop() {
+                self.len -= 1;
+                return Some((self.lowest, item));
+            }
+            self.lowest += 1;
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
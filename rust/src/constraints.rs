@@ -0,0 +1,92 @@
+use crate::board::COLOURS;
+use crate::board::Colour;
+use crate::colour_to_int;
+use crate::state::State;
+
+// Bits of a state's aux progress constraints use, above the rules': the moves
+// each robot with an allowance has made, three bits a robot in colour order,
+// then the moves the helpers have made between them.
+const COUNT_SHIFT: u32 = 5;
+const COUNT_BITS: u32 = 3;
+const HELPER_SHIFT: u32 = COUNT_SHIFT + 4 * COUNT_BITS;
+const HELPER_BITS: u32 = 4;
+
+// The largest allowances states have room to count up to.
+pub const MAX_ALLOWANCE: u8 = (1 << COUNT_BITS) - 1;
+pub const MAX_HELPER_MOVES: u8 = (1 << HELPER_BITS) - 1;
+
+// Which robots a solve may move, and how often. Any robot may move any
+// number of times by default.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Constraints {
+    // Robots that may not move at all, in colour order.
+    pub forbidden: [bool; 4],
+    // Most moves each robot may make, in colour order.
+    pub allowances: [Option<u8>; 4],
+    // Most moves the robots other than the target may make between them.
+    pub helper_moves: Option<u8>,
+}
+
+impl Constraints {
+    // Only the robots in `colours` may move.
+    pub fn only(colours: &[Colour]) -> Constraints {
+        let mut forbidden = [true; 4];
+        for colour in colours {
+            forbidden[colour_to_int(*colour) as usize] = false;
+        }
+        Constraints { forbidden, ..Constraints::default() }
+    }
+
+    pub fn any(&self) -> bool {
+        *self != Constraints::default()
+    }
+
+    // Whether the counts fit the bits states keep them in. Larger ones
+    // would have to be cut down, which changes what can be solved.
+    pub fn validate(&self) -> Result<(), String> {
+        for (colour, allowance) in COLOURS.iter().zip(self.allowances.iter()) {
+            if allowance.is_some_and(|allowance| allowance > MAX_ALLOWANCE) {
+                return Err(format!("{:?} can be allowed at most {} moves", colour, MAX_ALLOWANCE));
+            }
+        }
+        if self.helper_moves.is_some_and(|allowance| allowance > MAX_HELPER_MOVES) {
+            return Err(format!("helpers can be allowed at most {} moves", MAX_HELPER_MOVES));
+        }
+        Ok(())
+    }
+
+    // Whether the robot of `colour` may make another move from `state`.
+    pub fn allows(&self, state: State, colour: Colour, target: Colour) -> bool {
+        let robot = colour_to_int(colour) as usize;
+        if self.forbidden[robot] {
+            return false;
+        }
+        if self.allowances[robot].is_some_and(|allowance| robot_moves(state, robot) >= allowance) {
+            return false;
+        }
+        robot == colour_to_int(target) as usize
+            || self.helper_moves.is_none_or(|allowance| helper_moves(state) < allowance)
+    }
+
+    // `after`, the board once the robot of `colour` has moved, with one more
+    // move counted against the allowances it has.
+    pub fn advance(&self, after: State, colour: Colour, target: Colour) -> State {
+        let robot = colour_to_int(colour) as usize;
+        let mut aux = after.aux();
+        if self.allowances[robot].is_some() {
+            aux += 1 << (COUNT_SHIFT + robot as u32 * COUNT_BITS);
+        }
+        if self.helper_moves.is_some() && robot != colour_to_int(target) as usize {
+            aux += 1 << HELPER_SHIFT;
+        }
+        after.with_aux(aux)
+    }
+}
+
+fn robot_moves(state: State, robot: usize) -> u8 {
+    (state.aux() >> (COUNT_SHIFT + robot as u32 * COUNT_BITS) & MAX_ALLOWANCE as u32) as u8
+}
+
+fn helper_moves(state: State) -> u8 {
+    (state.aux() >> HELPER_SHIFT & MAX_HELPER_MOVES as u32) as u8
+}
@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use hashbrown::HashMap;
+
+use crate::board::DIRECTIONS;
+use crate::board::EmptyBoard;
+use crate::board::Position;
+
+pub const UNREACHABLE: i8 = i8::MAX;
+
+pub type DistanceMap = [[i8; 16]; 16];
+
+// Lower bound on the number of moves a robot on each tile needs to reach
+// `goal`, or UNREACHABLE.
+//
+// A move slides the robot in a straight line, and counts as reaching every
+// tile it passes, not only the tile against the wall. That's on purpose:
+// another robot could be in the way at any of those tiles, so only moves of
+// this kind make the map a lower bound for boards with robots on them. It's
+// why these values can be smaller than the stop-based distances of the
+// Python version.
+pub fn distance_map(board: &EmptyBoard, goal: Position) -> DistanceMap {
+    let mut distances = [[UNREACHABLE; 16]; 16];
+    distances[goal.x as usize][goal.y as usize] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(goal);
+
+    // Breadth first out from the goal: every tile that can slide through a
+    // tile at distance n, and isn't closer already, is at distance n + 1.
+    while let Some(position) = queue.pop_front() {
+        let distance = distances[position.x as usize][position.y as usize] + 1;
+        for direction in DIRECTIONS.iter() {
+            let towards = direction.opposite();
+            let mut from = position;
+            while board.can_move(from, *direction) {
+                let next = board.get_tile(from).get_adjacent_position(*direction);
+                if !board.can_move(next, towards) {
+                    break;
+                }
+                from = next;
+                if distances[from.x as usize][from.y as usize] == UNREACHABLE {
+                    distances[from.x as usize][from.y as usize] = distance;
+                    queue.push_back(from);
+                }
+            }
+        }
+    }
+    distances
+}
+
+// Distance maps by map and goal, so runs over several goals, or several runs
+// over one goal, compute each map once.
+#[derive(Default)]
+pub struct DistanceCache {
+    maps: HashMap<(u64, Position), Rc<DistanceMap>>,
+}
+
+impl DistanceCache {
+    pub fn new() -> DistanceCache {
+        DistanceCache::default()
+    }
+
+    pub fn get(&mut self, board: &EmptyBoard, goal: Position) -> Rc<DistanceMap> {
+        self.maps.entry((board.map_hash(), goal))
+            .or_insert_with(|| Rc::new(distance_map(board, goal)))
+            .clone()
+    }
+}
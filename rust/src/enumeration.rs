@@ -0,0 +1,52 @@
+use itertools::Itertools;
+
+use crate::board::Position;
+use crate::solver::SolveStats;
+use crate::tablebase::binomial;
+
+// Every placement of a target robot and `helper_count` interchangeable
+// helpers on `tiles`, no two robots sharing a tile. The target can sit
+// anywhere relative to its helpers, and each helper set comes out once, in
+// `tiles` order.
+pub fn placements(tiles: &[Position], helper_count: usize) -> impl Iterator<Item = (Position, Vec<Position>)> + '_ {
+    tiles.iter().flat_map(move |target| {
+        tiles.iter()
+            .filter(move |tile| *tile != target)
+            .cloned()
+            .combinations(helper_count)
+            .map(move |helpers| (*target, helpers))
+    })
+}
+
+pub fn placement_count(tile_count: usize, helper_count: usize) -> u64 {
+    if tile_count == 0 {
+        return 0;
+    }
+    tile_count as u64 * binomial(tile_count as u64 - 1, helper_count as u64)
+}
+
+// What happened to each placement of an enumeration run.
+#[derive(Debug, Default)]
+pub struct EnumerationCounts {
+    pub expected: u64,
+    pub enumerated: u64,
+    pub solved: u64,
+    pub from_known_solutions: u64,
+    pub from_tablebase: u64,
+    // Totals over every placement that needed a search.
+    pub search: SolveStats,
+}
+
+impl EnumerationCounts {
+    pub fn new(tile_count: usize, helper_count: usize) -> EnumerationCounts {
+        EnumerationCounts {
+            expected: placement_count(tile_count, helper_count),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.enumerated == self.expected
+            && self.solved + self.from_known_solutions + self.from_tablebase == self.enumerated
+    }
+}
@@ -0,0 +1,123 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::analysis::MapAnalysis;
+use crate::board::Board;
+use crate::board::COLOURS;
+use crate::board::Colour;
+use crate::board::Position;
+use crate::goal::GoalSpec;
+use crate::heuristic::Heuristic;
+use crate::solver::AStar;
+use crate::solver::Limits;
+use crate::solver::Solution;
+use crate::solver::SolveStats;
+use crate::solver::Solver;
+use crate::solver::StopReason;
+
+// One round's target: the tile and the robot that has to stop on it.
+#[derive(Debug, Copy, Clone)]
+pub struct Target {
+    pub position: Position,
+    pub colour: Colour,
+}
+
+// The targets of a game in a random order, the same for the same seed. Like
+// the chips of the board game, each tile comes up once, always in the same
+// colour, the tiles taking the colours in turn.
+pub fn shuffled_targets(tiles: &[Position], seed: u64) -> Vec<Target> {
+    let mut targets: Vec<Target> = tiles.iter().zip(COLOURS.iter().cycle())
+        .map(|(position, colour)| Target { position: *position, colour: *colour })
+        .collect();
+    targets.shuffle(&mut StdRng::seed_from_u64(seed));
+    targets
+}
+
+// Targets from a script, one a line as a colour and the x and y of the tile,
+// like "red 4 1". Blank lines and lines starting with # are skipped, and
+// tiles robots can't reach on the map of `analysis` are turned down.
+pub fn parse_script(script: &str, analysis: &MapAnalysis) -> Result<Vec<Target>, String> {
+    let mut targets = Vec::new();
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let colour = fields.first().and_then(|name| parse_colour(name));
+        let x = fields.get(1).and_then(|x| x.parse::<i8>().ok()).filter(|x| (0..16).contains(x));
+        let y = fields.get(2).and_then(|y| y.parse::<i8>().ok()).filter(|y| (0..16).contains(y));
+        match (colour, x, y, fields.len()) {
+            (Some(colour), Some(x), Some(y), 3) => {
+                let position = Position { x, y };
+                analysis.validate(&[], position).map_err(|error| format!("line {}: {}", i + 1, error))?;
+                targets.push(Target { position, colour });
+            },
+            _ => return Err(format!("line {}: expected a colour and a tile, got \"{}\"", i + 1, line)),
+        }
+    }
+    Ok(targets)
+}
+
+fn parse_colour(name: &str) -> Option<Colour> {
+    match name.to_lowercase().as_str() {
+        "red" => Some(Colour::RED),
+        "green" => Some(Colour::GREEN),
+        "blue" => Some(Colour::BLUE),
+        "yellow" => Some(Colour::YELLOW),
+        _ => None,
+    }
+}
+
+// How one round went.
+pub struct Round {
+    pub target: Target,
+    // Where the robots stood when the target came up.
+    pub robots: Vec<Position>,
+    pub solution: Option<Solution>,
+    // Set if there was no solution within the limits, rather than none at all.
+    pub stopped: Option<StopReason>,
+    pub stats: SolveStats,
+}
+
+// A whole game: each target solved from wherever the last round left the
+// robots, as they aren't put back between rounds. A round with no solution
+// leaves them where they were.
+pub struct Game {
+    pub rounds: Vec<Round>,
+}
+
+impl Game {
+    pub fn play(
+        board: &Board,
+        targets: &[Target],
+        limits: &Limits,
+        heuristic_for: &mut dyn FnMut(Position) -> Box<dyn Heuristic>,
+    ) -> Game {
+        let mut current = board.clone();
+        let mut rounds = Vec::with_capacity(targets.len());
+        for target in targets {
+            let round_board = current.with_goal(target.position);
+            let heuristic = heuristic_for(target.position);
+            let result = AStar::new(&*heuristic).solve(&round_board, &GoalSpec::Robot(target.colour), limits);
+            current = match &result.solution {
+                Some(solution) => solution.replay(&round_board).pop().expect("replay starts from the board"),
+                None => round_board.clone(),
+            };
+            rounds.push(Round {
+                target: *target,
+                robots: round_board.get_robots(),
+                solution: result.solution,
+                stopped: result.stopped,
+                stats: result.stats,
+            });
+        }
+        Game { rounds }
+    }
+
+    // Moves over the rounds that were solved.
+    pub fn total_moves(&self) -> i64 {
+        self.rounds.iter().filter_map(|round| round.solution.as_ref()).map(|solution| solution.length() as i64).sum()
+    }
+}
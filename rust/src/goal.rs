@@ -0,0 +1,105 @@
+use crate::board::Colour;
+use crate::board::Position;
+use crate::heuristic::Heuristic;
+use crate::rules::finish_allowed;
+use crate::state::State;
+use crate::together::Together;
+use crate::tour::Tour;
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum VisitedKey {
+    // Boards are the same if every colour is on the same tile.
+    Exact,
+    // Boards are the same if the helpers stand on the same tiles, in any
+    // colours, where the goal allows it.
+    #[default]
+    Canonical,
+}
+
+// What counts as solving a board.
+#[derive(Debug, Copy, Clone)]
+pub enum GoalSpec {
+    // The robot of this colour stops on the board's goal.
+    Robot(Colour),
+    // The robot of the tour's colour stops on each of its checkpoints in
+    // turn, then on the board's goal.
+    Tour(Tour),
+    // Every robot of the set stands on its own target at once.
+    Together(Together),
+}
+
+impl GoalSpec {
+    pub fn is_solved(&self, goal: Position, state: State) -> bool {
+        match self {
+            GoalSpec::Robot(colour) => state.robot(*colour) == goal && finish_allowed(state),
+            GoalSpec::Tour(tour) => tour.is_complete(state) && state.robot(tour.colour) == goal && finish_allowed(state),
+            GoalSpec::Together(together) => together.is_placed(state) && finish_allowed(state),
+        }
+    }
+
+    // Whether the target robot has to move again from `state`: the goal
+    // isn't met, or for a set of robots, the lead isn't home yet.
+    pub fn target_must_move(&self, goal: Position, state: State) -> bool {
+        match self {
+            GoalSpec::Robot(_) | GoalSpec::Tour(_) => !self.is_solved(goal, state),
+            GoalSpec::Together(together) => state.robot(together.lead_colour()) != together.lead_target() || !finish_allowed(state),
+        }
+    }
+
+    // Whether the goal keeps progress of its own in states, which moves
+    // have to pass on with `advance`.
+    pub fn tracks_progress(&self) -> bool {
+        matches!(self, GoalSpec::Tour(_))
+    }
+
+    // `after`, the board once the robot of `colour` has moved, with the
+    // goal's progress moved on.
+    pub fn advance(&self, after: State, colour: Colour) -> State {
+        match self {
+            GoalSpec::Robot(_) | GoalSpec::Together(_) => after,
+            GoalSpec::Tour(tour) => tour.advance(after, colour),
+        }
+    }
+
+    // The key a search's visited set files `state` under. Canonical keys
+    // merge placements that only differ in which helper is which colour,
+    // when the goal doesn't care about the helpers' colours.
+    pub fn visited_key(&self, state: State, key: VisitedKey) -> State {
+        match (self, key) {
+            (GoalSpec::Robot(colour), VisitedKey::Canonical) => state.canonical(*colour),
+            (GoalSpec::Tour(tour), VisitedKey::Canonical) => state.canonical(tour.colour),
+            // Robots with targets keep their colours, and sorting the rest
+            // alone isn't worth it, so only a set of one merges anything.
+            (GoalSpec::Together(together), VisitedKey::Canonical) if together.count() == 1 => {
+                state.canonical(together.lead_colour())
+            },
+            (GoalSpec::Together(_), VisitedKey::Canonical) => state,
+            (_, VisitedKey::Exact) => state,
+        }
+    }
+
+    // The robot that has to reach the goal, or leads a set of them.
+    pub fn target_colour(&self) -> Colour {
+        match self {
+            GoalSpec::Robot(colour) => *colour,
+            GoalSpec::Tour(tour) => tour.colour,
+            GoalSpec::Together(together) => together.lead_colour(),
+        }
+    }
+
+    // The tile the target robot finishes on, given the board's goal.
+    pub fn target_position(&self, goal: Position) -> Position {
+        match self {
+            GoalSpec::Robot(_) | GoalSpec::Tour(_) => goal,
+            GoalSpec::Together(together) => together.lead_target(),
+        }
+    }
+
+    pub fn estimate(&self, heuristic: &dyn Heuristic, state: State) -> i16 {
+        match self {
+            GoalSpec::Robot(colour) => heuristic.evaluate(state, *colour),
+            GoalSpec::Tour(tour) => heuristic.evaluate(state, tour.colour),
+            GoalSpec::Together(together) => heuristic.evaluate(state, together.lead_colour()),
+        }
+    }
+}
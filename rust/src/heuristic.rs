@@ -0,0 +1,169 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::board::Colour;
+use crate::board::DIRECTIONS;
+use crate::board::EmptyBoard;
+use crate::board::Position;
+use crate::distance::DistanceMap;
+use crate::state::State;
+use crate::tablebase::Tablebase;
+use crate::transposition::DistanceTable;
+
+// Where a robot has to be for the target robot to stop on the goal, when no
+// wall stops it there. Empty if some slide onto the goal ends against a wall.
+pub struct Blockers {
+    tiles: Vec<Position>,
+}
+
+impl Blockers {
+    pub fn new(board: &EmptyBoard, goal: Position) -> Blockers {
+        let mut tiles = Vec::new();
+        for direction in DIRECTIONS.iter() {
+            // Slides in `direction` can only end on the goal if they can
+            // come in from the other side.
+            if !board.can_move(goal, direction.opposite()) {
+                continue;
+            }
+            if !board.can_move(goal, *direction) {
+                return Blockers { tiles: Vec::new() };
+            }
+            tiles.push(board.get_tile(goal).get_adjacent_position(*direction));
+        }
+        Blockers { tiles }
+    }
+
+    pub fn is_needed(&self) -> bool {
+        !self.tiles.is_empty()
+    }
+
+    // Whether a robot other than the target already stands where it would
+    // stop the target on the goal.
+    pub fn is_in_place(&self, robots: &[Position], target: Position) -> bool {
+        self.tiles.iter().any(|tile| *tile != target && robots.contains(tile))
+    }
+}
+
+// A lower bound on the number of moves left to get `target_colour` onto the
+// goal with the robots of `state`. The solver is only optimal if it never overestimates.
+pub trait Heuristic {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16;
+
+    // Estimates so far answered by a cache of known solution lengths.
+    fn cache_hits(&self) -> u64 {
+        0
+    }
+}
+
+// No estimate at all, which makes A* a breadth first search.
+pub struct Zero;
+
+impl Heuristic for Zero {
+    fn evaluate(&self, _state: State, _target_colour: Colour) -> i16 {
+        0
+    }
+}
+
+// Moves the target robot needs on its own.
+pub struct FloodFill {
+    distances: Rc<DistanceMap>,
+}
+
+impl FloodFill {
+    pub fn new(distances: Rc<DistanceMap>) -> FloodFill {
+        FloodFill { distances }
+    }
+}
+
+impl Heuristic for FloodFill {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16 {
+        let target = state.robot(target_colour);
+        self.distances[target.x as usize][target.y as usize] as i16
+    }
+}
+
+// As FloodFill, plus one when the goal needs a blocker and none of the other
+// robots is on a blocking tile yet: that robot has to move there at some
+// point, and the target robot's own moves don't count it.
+pub struct BlockerAware {
+    distances: Rc<DistanceMap>,
+    blockers: Blockers,
+}
+
+impl BlockerAware {
+    pub fn new(distances: Rc<DistanceMap>, blockers: Blockers) -> BlockerAware {
+        BlockerAware { distances, blockers }
+    }
+}
+
+impl Heuristic for BlockerAware {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16 {
+        let target = state.robot(target_colour);
+        let distance = self.distances[target.x as usize][target.y as usize] as i16;
+        if distance == 0 || !self.blockers.is_needed() {
+            return distance;
+        }
+        if self.blockers.is_in_place(&state.robots(), target) {
+            distance
+        } else {
+            distance + 1
+        }
+    }
+}
+
+// Exact lengths of boards solved before, keyed by `Board::hash`, falling
+// back to another heuristic for the rest.
+pub struct CachedDistances<'a> {
+    known_solutions: &'a dyn DistanceTable,
+    fallback: &'a dyn Heuristic,
+    hits: Cell<u64>,
+}
+
+impl<'a> CachedDistances<'a> {
+    pub fn new(known_solutions: &'a dyn DistanceTable, fallback: &'a dyn Heuristic) -> CachedDistances<'a> {
+        CachedDistances { known_solutions, fallback, hits: Cell::new(0) }
+    }
+}
+
+impl<'a> Heuristic for CachedDistances<'a> {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16 {
+        match self.known_solutions.get(state.key()) {
+            Some(distance) => {
+                self.hits.set(self.hits.get() + 1);
+                distance
+            },
+            None => self.fallback.evaluate(state, target_colour),
+        }
+    }
+
+    fn cache_hits(&self) -> u64 {
+        self.hits.get() + self.fallback.cache_hits()
+    }
+}
+
+// Exact lengths from a tablebase, falling back to another heuristic for
+// placements it doesn't know. The table has to match the board's map, goal
+// and target colour.
+pub struct TablebaseDistances<'a> {
+    tablebase: &'a Tablebase,
+    fallback: &'a dyn Heuristic,
+}
+
+impl<'a> TablebaseDistances<'a> {
+    pub fn new(tablebase: &'a Tablebase, fallback: &'a dyn Heuristic) -> TablebaseDistances<'a> {
+        TablebaseDistances { tablebase, fallback }
+    }
+}
+
+impl<'a> Heuristic for TablebaseDistances<'a> {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16 {
+        match self.tablebase.get(&state.robots()) {
+            Some(distance) => distance,
+            None => self.fallback.evaluate(state, target_colour),
+        }
+    }
+
+    fn cache_hits(&self) -> u64 {
+        self.fallback.cache_hits()
+    }
+}
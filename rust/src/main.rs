@@ -1,82 +1,96 @@
+mod analysis;
+mod batch;
+mod bitboard;
 mod board;
+mod bucket;
+mod constraints;
+mod distance;
+mod enumeration;
+mod game;
+mod goal;
+mod heuristic;
+mod optimal;
+mod pattern;
+mod pruning;
+mod rules;
+mod solver;
+mod state;
+mod tablebase;
+mod together;
+mod tour;
+mod transposition;
 
-use std::cmp::Ordering;
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
 use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
 use std::time::SystemTime;
 
-use itertools::Itertools;
-use rand::Rng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use hashbrown::HashMap;
 use thincollections::thin_vec::ThinVec;
 
+use analysis::MapAnalysis;
+use batch::BatchSolver;
 use board::Board;
+use board::COLOURS;
 use board::Colour;
-use board::Direction;
 use board::EmptyBoard;
 use board::Position;
-
-
-const FOUR: u64 = 4;
-
-struct PathOption {
-    priority: i16,
-    distance: i16,
-    board: Board,
-    directions: u64,
-    colours: u64,
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Move {
-    direction: Direction,
-    colour: Colour,
-}
-
-struct Solution {
-    length: i16,
-    path: PathOption,
-}
-
-impl Ord for PathOption {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.priority.cmp(&other.priority)
-    }
-}
-
-impl PartialOrd for PathOption {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl PartialEq for PathOption {
-    fn eq(&self, other: &Self) -> bool {
-        self.priority == other.priority
-    }
-}
-
-impl Eq for PathOption {}
-
-fn to_int (direction: Direction) -> u64 {
-    match direction {
-        Direction::UP => 0,
-        Direction::RIGHT => 1,
-        Direction::DOWN => 2,
-        Direction::LEFT => 3,
-    }
-}
-
-fn to_direction(num: u64) -> Direction {
-    match num {
-        0 => Direction::UP,
-        1 => Direction::RIGHT,
-        2 => Direction::DOWN,
-        3 => Direction::LEFT,
-        _ => panic!("dumb bumb"),
-    }
-}
+use distance::DistanceCache;
+use board::DEFAULT_MAP;
+use board::Map;
+use board::load_map;
+use constraints::Constraints;
+use constraints::MAX_HELPER_MOVES;
+use enumeration::EnumerationCounts;
+use enumeration::placements;
+use game::Game;
+use game::parse_script;
+use game::shuffled_targets;
+use goal::GoalSpec;
+use goal::VisitedKey;
+use heuristic::BlockerAware;
+use heuristic::Blockers;
+use heuristic::CachedDistances;
+use heuristic::FloodFill;
+use heuristic::Heuristic;
+use heuristic::TablebaseDistances;
+use heuristic::Zero;
+use optimal::AllOptimal;
+use pattern::PatternDatabase;
+use pruning::Pruning;
+use rules::Rules;
+use solver::AStar;
+use solver::Anytime;
+use solver::Beam;
+use solver::BreadthFirst;
+use solver::IdaStar;
+use solver::Limits;
+use solver::Progress;
+use solver::ProgressCallback;
+use solver::SolveStats;
+use solver::Solver;
+use solver::StopReason;
+use solver::TieBreak;
+use tablebase::Header;
+use tablebase::Tablebase;
+use together::Together;
+use together::TogetherDistances;
+use tour::Tour;
+use tour::TourDistances;
+use transposition::DistanceTable;
+use transposition::Replacement;
+use transposition::TranspositionTable;
+
+
+const TABLEBASE_PATH: &str = "tablebase.rrtb";
+// Fixed so timings of different runs compare the same boards.
+const BENCHMARK_SEED: u64 = 20;
 
 fn colour_to_int (colour: Colour) -> u64 {
     match colour {
@@ -97,138 +111,18 @@ fn to_colour(num: u64) -> Colour {
     }
 }
 
-fn _flood_fill(current_position: Position, mut map: [[i8; 16]; 16], board: EmptyBoard, mut count: i8) -> [[i8; 16]; 16] {
-    if count >= map[current_position.x as usize][current_position.y as usize] {
-        return map;
-    }
-    map[current_position.x as usize][current_position.y as usize] = count;
-    count += 1;
-
-    for direction in board.get_valid_directions(current_position) {
-        let mut position = current_position.clone();
-        while board.can_move(position, direction) {
-            position = board.get_tile(position).get_adjacent_position(direction);
-            map = _flood_fill(position.clone(), map, board, count);
-        }
-    }
-    return map
-}
-
-fn flood_fill(goal: Position) -> [[i8; 16]; 16] {
-    let map = [[std::i8::MAX; 16]; 16];
-    let board = EmptyBoard::new(goal.clone());
-    return _flood_fill(goal, map, board, 0);
-}
-
-// #[inline(never)]
-// fn get_neighbours(option: PathOption, flooded_map: &[[i8; 16]; 16], target_colour: Colour, known_solutions: &HashMap<u64, i16>) -> ThinVec<PathOption> {
-//     let mut neighbours = ThinVec::new();
-//     for colour in vec![Colour::RED, Colour::BLUE, Colour::YELLOW, Colour::GREEN] {
-//         for direction in option.board.get_valid_directions(colour) {
-//             let mut neighbour_board = option.board.clone();
-//             let new_position = neighbour_board.move_robot(colour, direction);
-//             let mut heuristic = flooded_map[new_position.x as usize][new_position.y as usize] as i16;
-
-//             match known_solutions.get(&neighbour_board.hash()) {
-//                 Some(distance) => {
-//                     heuristic = *distance;
-//                 },
-//                 None => {},
-//             }
-
-//             let neighbour = PathOption{
-//                 priority: option.distance + heuristic + 1,
-//                 distance: option.distance + 1,
-//                 board: neighbour_board,
-//                 directions: option.directions + FOUR.pow(option.distance as u32 + 1) * to_int(direction),
-//                 colours: option.colours + FOUR.pow(option.distance as u32 + 1) * colour_to_int(colour),
-//             };
-//             neighbours.push(neighbour);
-//         }
-//     }
-//     println!("Length: {}", neighbours.len());
-//     return neighbours
-// }
-
-#[inline(never)]
-fn solve(board: Board, target_colour: Colour, known_solutions: &HashMap<u64, i16>, flooded_map: &[[i8; 16]; 16]) -> Solution {
-    let mut heap = BinaryHeap::new();
-    let mut visited_board_states = HashMap::new();
-    heap.push(Reverse(PathOption{
-        priority: 0,
-        distance: 0,
-        board: board,
-        directions: 0,
-        colours: 0,
-    }));
-
-    loop {
-        let some_option = heap.pop();
-        match some_option {
-            Some(Reverse(option)) => {
-                if option.board.is_solved(target_colour) {
-                    return Solution {
-                        length: option.distance,
-                        path: option,
-                    }
-                }
-
-                for colour in vec![Colour::RED, Colour::BLUE, Colour::YELLOW, Colour::GREEN] {
-                    for direction in option.board.get_valid_directions(colour) {
-                        let mut neighbour_board = option.board.clone();
-                        let new_position = neighbour_board.move_robot(colour, direction);
-                        let mut heuristic = flooded_map[new_position.x as usize][new_position.y as usize] as i16;
-
-                        match known_solutions.get(&neighbour_board.hash()) {
-                            Some(distance) => {
-                                heuristic = *distance;
-                            },
-                            None => {},
-                        }
-
-                        let hashed_board = neighbour_board.hash();
-                        let mut push_state = false;
-                        match visited_board_states.get(&hashed_board) {
-                            Some(distance) => {
-                                if *distance > option.distance + 1 {
-                                    push_state = true;
-                                }
-                            },
-                            None => {
-                                push_state = true;
-                            },
-                        }
-                        if push_state {
-                            visited_board_states.insert(hashed_board, option.distance + 1);
-                            heap.push(Reverse(
-                                PathOption{
-                                    priority: option.distance + heuristic + 1,
-                                    distance: option.distance + 1,
-                                    board: neighbour_board,
-                                    directions: option.directions + FOUR.pow(option.distance as u32 + 1) * to_int(direction),
-                                    colours: option.colours + FOUR.pow(option.distance as u32 + 1) * colour_to_int(colour),
-                                }
-                            ));
-                        }
-                    }
-                }
-            },
-            None => continue,
-        }
-    }
-}
-
 fn main() {
     // let mut known_solutions: HashMap<u64, i16> = HashMap::new();
     // let mut board = Board::new(
     //     Position { x: 0, y: 0 }, Position { x: 2, y: 0 }, Position { x: 3, y: 0 }, Position { x: 4, y: 0 },
     //     Position{x: 6, y: 14},
     // );
-    // let flooded_board = flood_fill(Position{x: 6, y: 14});
+    // let heuristic = FloodFill::new(Rc::new(distance_map(&EmptyBoard::new(Position{x: 6, y: 14}), Position{x: 6, y: 14})));
 
     // let now = SystemTime::now();
-    // let result = solve(board.clone(), Colour::RED, &known_solutions, &flooded_board);
-    // println!("result: {}", result.length);
+    // let result = AStar::new(&CachedDistances::new(&known_solutions, &heuristic))
+    //     .solve(&board, &GoalSpec::Robot(Colour::RED), &Limits::default());
+    // println!("result: {:?}", result.solution.map(|solution| solution.length()));
     // match now.elapsed() {
     //     Ok(elapsed) => {
     //         println!("Time: {:?}", elapsed);
@@ -236,138 +130,878 @@ fn main() {
     //     Err(_) => println!("It broke somehow"),
     // }
 
-    
+    let args: Vec<String> = std::env::args().filter(|arg| !arg.starts_with("--")).collect();
+    let show_stats = std::env::args().any(|arg| arg == "--stats");
+    let mode = args.get(1).map(|arg| arg.as_str()).unwrap_or("enumerate");
+    let map_path = args.get(2).map(|arg| arg.as_str()).unwrap_or(DEFAULT_MAP);
+    let goal = Position{x: 6, y: 14};
+    let heuristic_name = args.get(3).map(|arg| arg.as_str()).unwrap_or("flood");
+    let solver_name = args.get(4).map(|arg| arg.as_str()).unwrap_or("astar");
+    let pruning_name = args.get(5).map(|arg| arg.as_str()).unwrap_or("all");
+    let pruning = match make_pruning(pruning_name) {
+        Some(pruning) => pruning,
+        None => {
+            println!("Unknown pruning {}", pruning_name);
+            return;
+        },
+    };
+    let visited_key = if std::env::args().any(|arg| arg == "--exact") { VisitedKey::Exact } else { VisitedKey::Canonical };
+    let tie_break = if std::env::args().any(|arg| arg == "--tie-break") { TieBreak::FewestRobots } else { TieBreak::FirstFound };
+    let replacement = if std::env::args().any(|arg| arg == "--always-replace") { Replacement::AlwaysReplace } else { Replacement::DepthPreferred };
+    let progress = ProgressCallback(Arc::new(|progress: &Progress| {
+        println!(
+            "Bound {}: {} expanded, {} generated, {:?}",
+            progress.bound, progress.nodes_expanded, progress.nodes_generated, progress.elapsed,
+        );
+    }));
+    let helper_moves = match flag_value("--helper-moves").map(u8::try_from).transpose() {
+        Ok(helper_moves) => helper_moves,
+        Err(_) => {
+            println!("Helpers can be allowed at most {} moves", MAX_HELPER_MOVES);
+            return;
+        },
+    };
+    let limits = Limits {
+        max_depth: flag_value("--max-depth").map(|depth| depth as i16),
+        max_nodes: flag_value("--max-nodes").map(|nodes| nodes as u64),
+        max_time: flag_value("--time-ms").map(|millis| Duration::from_millis(millis as u64)),
+        progress: std::env::args().any(|arg| arg == "--progress").then_some(progress),
+        pruning,
+        visited_key,
+        visited_bytes: flag_value("--visited-mb").map(|megabytes| megabytes << 20),
+        replacement,
+        tie_break,
+        constraints: Constraints {
+            helper_moves,
+            ..Constraints::default()
+        },
+        rules: Rules {
+            leave_goal_first: std::env::args().any(|arg| arg == "--official-rules"),
+            ricochet: std::env::args().any(|arg| arg == "--ricochet"),
+        },
+        ..Limits::default()
+    };
+    if let Err(error) = limits.constraints.validate() {
+        println!("Bad constraints: {}", error);
+        return;
+    }
+    let cache_bytes = flag_value("--cache-mb").map(|megabytes| megabytes << 20);
+    let mut distance_cache = DistanceCache::new();
+    match mode {
+        "enumerate" => enumerate(map_path, goal, heuristic_name, &limits, cache_bytes, &mut distance_cache),
+        "random" => random_boards(map_path, goal, 1000, &[heuristic_name], solver_name, &[("default", limits)], show_stats, &mut distance_cache),
+        "heuristics" => random_boards(map_path, goal, 1000, &["flood", "blocker", "pattern"], "astar", &[("default", limits)], show_stats, &mut distance_cache),
+        "pruning" => {
+            let configs: Vec<(&str, Limits)> = ["none", "reversals", "commuting", "all"].iter()
+                .map(|name| (*name, Limits { pruning: make_pruning(name).expect("known pruning"), ..limits.clone() }))
+                .collect();
+            random_boards(map_path, goal, 1000, &[heuristic_name], solver_name, &configs, show_stats, &mut distance_cache)
+        },
+        "keys" => {
+            let configs = [
+                ("exact keys", Limits { visited_key: VisitedKey::Exact, ..limits.clone() }),
+                ("canonical keys", Limits { visited_key: VisitedKey::Canonical, ..limits.clone() }),
+            ];
+            random_boards(map_path, goal, 1000, &[heuristic_name], solver_name, &configs, show_stats, &mut distance_cache)
+        },
+        "ties" => {
+            let configs = [
+                ("first found", Limits { tie_break: TieBreak::FirstFound, ..limits.clone() }),
+                ("fewest robots", Limits { tie_break: TieBreak::FewestRobots, ..limits.clone() }),
+            ];
+            random_boards(map_path, goal, 1000, &[heuristic_name], solver_name, &configs, show_stats, &mut distance_cache)
+        },
+        "rules" => compare_rules(map_path, goal, heuristic_name, &limits, &mut distance_cache),
+        "constraints" => compare_constraints(map_path, goal, heuristic_name, &limits, &mut distance_cache),
+        "live" => live_play(map_path, goal, heuristic_name, &limits, &mut distance_cache),
+        "unique" => count_optimal_solutions(map_path, goal, heuristic_name, &limits, flag_value("--cap").unwrap_or(100), &mut distance_cache),
+        "tour" => solve_tours(map_path, goal, heuristic_name, solver_name, &limits, flag_value("--checkpoints").unwrap_or(2), &mut distance_cache),
+        "together" => solve_together(map_path, goal, heuristic_name, solver_name, &limits, flag_value("--robots").unwrap_or(2), &mut distance_cache),
+        "game" => play_games(map_path, goal, heuristic_name, &limits, flag_value("--games").unwrap_or(20), &mut distance_cache),
+        "targets" => solve_all_targets(map_path, goal, heuristic_name, &limits, flag_value("--sweep-nodes").unwrap_or(50_000) as u64, &mut distance_cache),
+        _ => println!("Usage: tempo [enumerate|random|heuristics|pruning|keys|ties|rules|constraints|live|unique|targets|game|tour|together] [map file] [zero|flood|blocker|pattern] [astar|bfs|ida|beam|anytime] [all|none|reversals|commuting] [--stats] [--exact] [--visited-mb=N] [--cache-mb=N] [--always-replace] [--max-depth=N] [--max-nodes=N] [--time-ms=N] [--progress] [--cap=N] [--tie-break] [--official-rules] [--ricochet] [--helper-moves=N] [--sweep-nodes=N] [--games=N] [--seed=N] [--script=file] [--checkpoints=N] [--robots=N]"),
+    }
+}
 
-    let unsolveable_positions = vec![
-        Position{x: 7, y: 7},
-        Position{x: 7, y: 8},
-        Position{x: 8, y: 7},
-        Position{x: 8, y: 8},
-    ];
-    let mut board_positions = vec![];
-    for y in 0..16 {
-        for x in 0..16 {
-            let position = Position{x: x, y: y};
-            if !unsolveable_positions.contains(&position) {
-                board_positions.push(position);
-            }
-        }
+// The number in a `--name=N` argument.
+fn flag_value(name: &str) -> Option<usize> {
+    flag_text(name).and_then(|value| value.parse().ok())
+}
+
+// What follows the `=` in a `--name=value` argument.
+fn flag_text(name: &str) -> Option<String> {
+    std::env::args()
+        .find_map(|arg| arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')).map(|value| value.to_string()))
+}
+
+fn enumerate(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, cache_bytes: Option<usize>, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let analysis = MapAnalysis::new(&empty_board);
+    if let Err(error) = analysis.validate(&[], goal) {
+        println!("Can't enumerate: {}", error);
+        return;
     }
-    let goal = Position{x: 6, y: 14};
-    let mut known_solutions: HashMap<u64, i16> = HashMap::new();
+    // The cache and tablebase file lengths by robots alone, which is only
+    // right if no rules or constraints keep progress of their own.
+    if limits.rules.any() || limits.constraints.any() {
+        println!("Can't enumerate under rules or constraints: cached lengths don't record their progress");
+        return;
+    }
+    println!(
+        "Unreachable tiles: {:?}, tiles that need a blocker: {}",
+        analysis.unreachable_tiles(), analysis.blocker_tiles().len(),
+    );
+    let board_positions = analysis.reachable_tiles();
+    // Lengths of every board on a solved path, as a map that keeps them
+    // all or, given a budget, a table that forgets some once full.
+    let mut cache_table = cache_bytes.map(|bytes| TranspositionTable::new(bytes, limits.replacement));
+    let mut cache_map: HashMap<u64, i16> = HashMap::new();
+    let known_solutions: &mut dyn DistanceTable = match &mut cache_table {
+        Some(table) => table,
+        None => &mut cache_map,
+    };
     let mut longest_solution = 0;
     let mut longest_solution_start: Vec<Position> = Vec::new();
-    let mut count: u64 = 0; 
+    let mut counts = EnumerationCounts::new(board_positions.len(), 3);
+
+    let map_hash = empty_board.map_hash();
+    let tablebase = match Tablebase::open(TABLEBASE_PATH) {
+        Ok(table) => {
+            if table.matches(map_hash, goal, Colour::RED) {
+                Some(table)
+            } else {
+                println!("Ignoring {}, it was built for another map or goal", TABLEBASE_PATH);
+                None
+            }
+        },
+        Err(_) => None,
+    };
 
     let start = SystemTime::now();
-    let flooded_board = flood_fill(goal);
-    for robots in board_positions.into_iter().combinations(4) {
-        count += 1;
-        let mut board = Board::new(
-            robots[0],
-            robots[1],
-            robots[2],
-            robots[3],
+    let heuristic = match make_heuristic(heuristic_name, &empty_board, goal, distance_cache) {
+        Some(heuristic) => heuristic,
+        None => {
+            println!("Unknown heuristic {}", heuristic_name);
+            return;
+        },
+    };
+    for (red, helpers) in placements(&board_positions, 3) {
+        counts.enumerated += 1;
+        let board = Board::with_map(
+            map.clone(),
+            red,
+            helpers[0],
+            helpers[1],
+            helpers[2],
             goal,
         );
 
-        if known_solutions.contains_key(&board.hash()) {
+        if known_solutions.get(board.hash()).is_some() {
+            counts.from_known_solutions += 1;
             continue;
         }
-
-        let result = solve(board.clone(), Colour::RED, &known_solutions, &flooded_board);
-
-        let mut idx = result.length;
-        let mut direction_path = result.path.directions;
-        let mut colour_path = result.path.colours;
-
-        for hash in &board.permuted_hashes() {
-            known_solutions.insert(*hash, idx);
+        if let Some(length) = tablebase.as_ref().and_then(|table| table.get(&board.get_robots())) {
+            counts.from_tablebase += 1;
+            if length > longest_solution {
+                longest_solution = length;
+                longest_solution_start = board.get_robots();
+            }
+            continue;
         }
-        while direction_path > 0 {
-            let factor = FOUR.pow(idx as u32);
-            let direction_num = direction_path / factor;
-            direction_path = direction_path - (direction_num * factor);
-            let direction = to_direction(direction_num);
-
-            let colour_num = colour_path / factor;
-            colour_path = colour_path - (colour_num * factor);
-            let colour = to_colour(colour_num);
-            idx -= 1;
-
-            board.move_robot(colour, direction);
-            for hash in &board.permuted_hashes() {
-                known_solutions.insert(*hash, idx);
+
+        let goal_spec = GoalSpec::Robot(Colour::RED);
+        let result = match &tablebase {
+            Some(table) => {
+                let from_table = TablebaseDistances::new(table, &*heuristic);
+                AStar::new(&CachedDistances::new(&*known_solutions, &from_table)).solve(&board, &goal_spec, limits)
+            },
+            None => AStar::new(&CachedDistances::new(&*known_solutions, &*heuristic)).solve(&board, &goal_spec, limits),
+        };
+        let solution = match result.solution {
+            Some(solution) => solution,
+            None => {
+                println!("No solution for {:?}", board.get_robots());
+                continue;
+            },
+        };
+        counts.solved += 1;
+        counts.search.add(&result.stats);
+        let start_robots = board.get_robots();
+
+        // Every board along an optimal solution is that many moves from the
+        // end of it, whichever robots play the helpers.
+        let length = solution.length();
+        for (i, step) in solution.replay(&board).iter().enumerate() {
+            for hash in &step.permuted_hashes() {
+                known_solutions.insert(*hash, length - i as i16);
             }
         }
 
-        if result.length > longest_solution {
-            println!("New longest: {}", result.length);
-            longest_solution = result.length;
-            longest_solution_start = robots.clone();
+        if length > longest_solution {
+            println!("New longest: {}", length);
+            longest_solution = length;
+            longest_solution_start = start_robots;
         }
-        if count % 10000 == 0 {
+        if counts.enumerated % 10000 == 0 {
             match start.elapsed() {
                 Ok(elapsed) => {
                     println!("Time: {:?}", elapsed);
-                    println!("Average {}ms", elapsed.as_millis() as f64 / count as f64);
+                    println!("Average {}ms", elapsed.as_millis() as f64 / counts.enumerated as f64);
                 },
                 Err(_) => println!("It broke somehow"),
             }
-            println!("Checked {} boards", count);
+            println!("Checked {} of {} boards", counts.enumerated, counts.expected);
         }
     }
+    println!(
+        "Enumerated {} of {} placements: {} solved, {} from known solutions, {} from the tablebase",
+        counts.enumerated, counts.expected, counts.solved, counts.from_known_solutions, counts.from_tablebase,
+    );
+    if !counts.is_complete() {
+        println!("Enumeration didn't cover every placement, the longest below is only a lower bound");
+    }
     println!("Final longest: {}, {:?}", longest_solution, longest_solution_start);
+    print_search_stats(&counts.search, counts.solved);
+
+    let header = Header::new(map_hash, goal, Colour::RED, 4);
+    match Tablebase::write(TABLEBASE_PATH, header, &*known_solutions, tablebase.as_ref()) {
+        Ok(written) => println!("Wrote {} entries to {}", written, TABLEBASE_PATH),
+        Err(error) => println!("Couldn't write {}: {}", TABLEBASE_PATH, error),
+    }
+    if let Some(table) = &cache_table {
+        let stats = table.stats();
+        println!(
+            "Known solutions table of {} slots: {} hits, {} misses, {} stores, {} evictions, {} rejected",
+            table.capacity(), stats.hits, stats.misses, stats.stores, stats.evictions, stats.rejections,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn random_boards(
+    map_path: &str,
+    goal: Position,
+    board_count: u32,
+    heuristic_names: &[&str],
+    solver_name: &str,
+    configs: &[(&str, Limits)],
+    show_stats: bool,
+    distance_cache: &mut DistanceCache,
+) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, board_count);
+    if boards.is_empty() {
+        return;
+    }
+
+    let mut lengths: Vec<i16> = Vec::new();
+    for name in heuristic_names {
+        let heuristic = match make_heuristic(name, &empty_board, goal, distance_cache) {
+            Some(heuristic) => heuristic,
+            None => {
+                println!("Unknown heuristic {}", name);
+                continue;
+            },
+        };
+        let solver = match make_solver(solver_name, &*heuristic) {
+            Some(solver) => solver,
+            None => {
+                println!("Unknown solver {}", solver_name);
+                return;
+            },
+        };
+        for (config_name, limits) in configs {
+            let goal_spec = GoalSpec::Robot(Colour::RED);
+            let mut total_stats = SolveStats::default();
+            let mut total_solve_length: u128 = 0;
+            let mut total_robots_moved = 0;
+            let mut total_target_moves = 0;
+            let mut mismatches = 0;
+            for (i, board) in boards.iter().enumerate() {
+                let result = solver.solve(board, &goal_spec, limits);
+                total_stats.add(&result.stats);
+                let length = match result.solution {
+                    Some(solution) => {
+                        if !solution.verify(board, &goal_spec, &limits.rules) {
+                            println!("Invalid solution: {:?}", solution.moves);
+                        }
+                        total_robots_moved += solution.robots_moved();
+                        total_target_moves += solution.moves_of(Colour::RED);
+                        solution.length()
+                    },
+                    None => {
+                        println!("No solution: {:?}", result.stopped);
+                        -1
+                    },
+                };
+                println!("{}, {:?}", length, result.stats.wall_time.as_millis());
+                total_solve_length += length.max(0) as u128;
+                if lengths.len() <= i {
+                    lengths.push(length);
+                } else if lengths[i] != length {
+                    mismatches += 1;
+                }
+            }
+            let count = boards.len() as u128;
+            println!("Heuristic: {}, solver: {}, {}", name, solver_name, config_name);
+            println!("Average solve time: {}ms", total_stats.wall_time.as_micros() as f64 / count as f64 / 1000.0);
+            println!("Average solve length: {}", total_solve_length as f64 / count as f64);
+            println!("Average boards expanded: {}", total_stats.nodes_expanded as f64 / count as f64);
+            println!(
+                "Average robots moved: {}, target robot moves: {}",
+                total_robots_moved as f64 / count as f64, total_target_moves as f64 / count as f64,
+            );
+            if show_stats {
+                print_search_stats(&total_stats, boards.len() as u64);
+                println!(
+                    "Moves pruned: {} reversals, {} commuting",
+                    total_stats.pruned_reversals, total_stats.pruned_commuting,
+                );
+                if limits.visited_bytes.is_some() {
+                    let table = &total_stats.visited_table;
+                    println!(
+                        "Visited table: {} hits, {} misses, {} evictions, {} rejected",
+                        table.hits, table.misses, table.evictions, table.rejections,
+                    );
+                }
+            }
+            if mismatches > 0 {
+                println!("{} solve lengths differ from the first run", mismatches);
+            }
+        }
+    }
+}
 
+// The same `board_count` random boards every run, less any that can't be
+// solved.
+fn benchmark_boards(map: &Rc<Map>, empty_board: &EmptyBoard, goal: Position, board_count: u32) -> Vec<Board> {
+    let analysis = MapAnalysis::new(empty_board);
+    let mut boards = Vec::new();
+    let mut rng = StdRng::seed_from_u64(BENCHMARK_SEED);
+    for _ in 0..board_count {
+        let robots = analysis.random_placement(&mut rng, 4);
+        if let Err(error) = analysis.validate(&robots, goal) {
+            println!("Skipping board: {}", error);
+            continue;
+        }
+        boards.push(Board::with_map(
+            map.clone(),
+            robots[0],
+            robots[1],
+            robots[2],
+            robots[3],
+            goal,
+        ));
+    }
+    boards
+}
 
+// Anytime solves under a time limit, 200ms unless `limits` sets one, as live
+// play needs: how soon a first answer comes, how often the last is proven
+// shortest, and how much longer than the shortest it is otherwise.
+fn live_play(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 1000);
+    let heuristic = match make_heuristic(heuristic_name, &empty_board, goal, distance_cache) {
+        Some(heuristic) => heuristic,
+        None => {
+            println!("Unknown heuristic {}", heuristic_name);
+            return;
+        },
+    };
+    let limits = Limits { max_time: limits.max_time.or(Some(Duration::from_millis(200))), ..limits.clone() };
+    let goal_spec = GoalSpec::Robot(Colour::RED);
+    let anytime = Anytime::new(&*heuristic);
+    let exact = AStar::new(&*heuristic);
+    let mut first_answer = Duration::ZERO;
+    let mut unanswered = 0;
+    let mut proven = 0;
+    let mut extra_moves = 0;
+    for board in &boards {
+        let mut first: Option<Duration> = None;
+        let result = anytime.solve_improving(board, &goal_spec, &limits, &mut |improvement| {
+            println!("{}, {:?}, {}", improvement.solution.length(), improvement.elapsed, if improvement.optimal { "optimal" } else { "so far" });
+            first.get_or_insert(improvement.elapsed);
+        });
+        let solution = match result.solution {
+            Some(solution) => solution,
+            None => {
+                println!("No answer: {:?}", result.stopped);
+                unanswered += 1;
+                continue;
+            },
+        };
+        if !solution.verify(board, &goal_spec, &limits.rules) {
+            println!("Invalid solution: {:?}", solution.moves);
+        }
+        first_answer += first.unwrap_or_default();
+        if result.stopped.is_none() {
+            proven += 1;
+        } else if let Some(shortest) = exact.solve(board, &goal_spec, &Limits { max_time: None, ..limits.clone() }).solution {
+            extra_moves += solution.length() - shortest.length();
+        }
+    }
+    let answered = boards.len() - unanswered;
+    println!("Answered {} of {} boards within {:?}, {} proven shortest", answered, boards.len(), limits.max_time.unwrap_or_default(), proven);
+    if answered > 0 {
+        println!("Average time to a first answer: {:?}", first_answer / answered as u32);
+    }
+    if answered > proven {
+        println!("Unproven answers were {} moves longer than the shortest in all", extra_moves);
+    }
+}
 
+// How many distinct shortest solutions the benchmark boards have, up to
+// `cap` each, and how many have just the one.
+fn count_optimal_solutions(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, cap: usize, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 1000);
+    let heuristic = match make_heuristic(heuristic_name, &empty_board, goal, distance_cache) {
+        Some(heuristic) => heuristic,
+        None => {
+            println!("Unknown heuristic {}", heuristic_name);
+            return;
+        },
+    };
+    let goal_spec = GoalSpec::Robot(Colour::RED);
+    let all_optimal = AllOptimal::new(&*heuristic, cap);
+    let mut unique = 0;
+    let mut capped = 0;
+    let mut total_stats = SolveStats::default();
+    for board in &boards {
+        let result = all_optimal.solve(board, &goal_spec, limits);
+        total_stats.add(&result.stats);
+        for solution in &result.solutions {
+            if !solution.verify(board, &goal_spec, &limits.rules) {
+                println!("Invalid solution: {:?}", solution.moves);
+            }
+        }
+        println!("{:?}, {} solutions, {:?}", result.length, result.solutions.len(), result.stopped);
+        if result.is_unique() {
+            unique += 1;
+            let moves: Vec<String> = result.solutions[0].moves.iter().map(|step| step.to_string()).collect();
+            println!("Unique: {}", moves.join(", "));
+        }
+        if result.stopped == Some(StopReason::SolutionCap) {
+            capped += 1;
+        }
+    }
+    println!("{} of {} boards have a unique shortest solution, {} have at least {}", unique, boards.len(), capped, cap);
+    print_search_stats(&total_stats, boards.len() as u64);
+}
 
-    // let unsolveable_positions = vec![
-    //     Position{x: 7, y: 7},
-    //     Position{x: 7, y: 8},
-    //     Position{x: 8, y: 7},
-    //     Position{x: 8, y: 8},
-    // ];
-    // let mut total: u128 = 0;
-    // let mut count: u128 = 0;
-    // let mut total_solve_length: u128 = 0;
-    // let mut known_solutions: HashMap<u64, i16> = HashMap::new();
-    // let flooded_board = flood_fill(Position{x: 6, y: 14});
-    // for _ in 0..1000 {
-    //     let mut rng = rand::thread_rng();
-    //     let pos1 = Position{x: rng.gen_range(0, 16), y: rng.gen_range(0, 16)};
-    //     let pos2 = Position{x: rng.gen_range(0, 16), y: rng.gen_range(0, 16)};
-    //     let pos3 = Position{x: rng.gen_range(0, 16), y: rng.gen_range(0, 16)};
-    //     let pos4 = Position{x: rng.gen_range(0, 16), y: rng.gen_range(0, 16)};
-    //     let pos5 = Position{x: rng.gen_range(0, 16), y: rng.gen_range(0, 16)};
-    //     let mut robot_positions = HashSet::new();
-    //     robot_positions.insert(pos1);
-    //     robot_positions.insert(pos2);
-    //     robot_positions.insert(pos3);
-    //     robot_positions.insert(pos4);
-    //     robot_positions.insert(pos5);
-    //     if robot_positions.len() != 5 || unsolveable_positions.contains(&pos1) || unsolveable_positions.contains(&pos2) || unsolveable_positions.contains(&pos3) || unsolveable_positions.contains(&pos4) || unsolveable_positions.contains(&pos5) {
-    //         continue;
-    //     }
-    //     let board = Board::new(
-    //         pos1,
-    //         pos2,
-    //         pos3,
-    //         pos4,
-    //         Position{x: 6, y: 14},
-    //     );
-    //     let now = SystemTime::now();
-    //     let result = solve(board, Colour::RED, &known_solutions, &flooded_board);
-    //     match now.elapsed() {
-    //         Ok(elapsed) => {
-    //             println!("{}, {:?}", result.length, elapsed.as_millis());
-    //             total += elapsed.as_millis();
-    //             total_solve_length += result.length as u128;
-    //             count += 1;
-    //         },
-    //         Err(_) => println!("It broke somehow"),
-    //     }
-    // }
-    // println!("Average solve time: {}", total/count);
-    // println!("Average solve length: {}", total_solve_length/count);
+// Red tours of the benchmark boards: stop on `checkpoint_count` corner tiles
+// picked at random for each board, in order, then on the goal. Prints each
+// solution with the moves that reach a checkpoint marked.
+fn solve_tours(map_path: &str, goal: Position, heuristic_name: &str, solver_name: &str, limits: &Limits, checkpoint_count: usize, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 20);
+    let tiles: Vec<Position> = MapAnalysis::new(&empty_board).corner_tiles().into_iter().filter(|tile| *tile != goal).collect();
+    let mut rng = StdRng::seed_from_u64(BENCHMARK_SEED);
+    let mut total_length = 0;
+    let mut solved = 0;
+    let mut total_stats = SolveStats::default();
+    for board in &boards {
+        let checkpoints: Vec<Position> = tiles.choose_multiple(&mut rng, checkpoint_count).cloned().collect();
+        let tour = match Tour::new(Colour::RED, &checkpoints) {
+            Ok(tour) => tour,
+            Err(error) => {
+                println!("Can't make a tour: {}", error);
+                return;
+            },
+        };
+        let heuristic: Box<dyn Heuristic> = match heuristic_name {
+            "zero" => Box::new(Zero),
+            "flood" => {
+                let legs = checkpoints.iter().chain(std::iter::once(&goal))
+                    .map(|stop| distance_cache.get(&EmptyBoard::with_map(*map, *stop), *stop))
+                    .collect();
+                Box::new(TourDistances::new(tour, legs))
+            },
+            _ => {
+                println!("Tours take the zero or flood heuristic, not {}", heuristic_name);
+                return;
+            },
+        };
+        let solver = match make_solver(solver_name, &*heuristic) {
+            Some(solver) => solver,
+            None => {
+                println!("Unknown solver {}", solver_name);
+                return;
+            },
+        };
+        let goal_spec = GoalSpec::Tour(tour);
+        let result = solver.solve(board, &goal_spec, limits);
+        total_stats.add(&result.stats);
+        let stops: Vec<String> = tour.checkpoints().iter().map(|checkpoint| checkpoint.to_string()).collect();
+        let solution = match result.solution {
+            Some(solution) => solution,
+            None => {
+                println!("Via {}: no solution, {:?}", stops.join(", "), result.stopped);
+                continue;
+            },
+        };
+        if !solution.verify(board, &goal_spec, &limits.rules) {
+            println!("Invalid solution: {:?}", solution.moves);
+        }
+        let states = solution.progress(board, &goal_spec, &limits.rules);
+        let moves: Vec<String> = solution.moves.iter().enumerate().map(|(i, step)| {
+            let reached = tour.progress(states[i + 1]);
+            if reached > tour.progress(states[i]) {
+                format!("{} [{}]", step, tour.checkpoints()[reached - 1])
+            } else {
+                step.to_string()
+            }
+        }).collect();
+        println!("{}, via {}: {}", solution.length(), stops.join(", "), moves.join(", "));
+        total_length += solution.length() as i64;
+        solved += 1;
+    }
+    println!("Average length {} over {} of {} tours", total_length as f64 / solved.max(1) as f64, solved, boards.len());
+    print_search_stats(&total_stats, boards.len() as u64);
+}
+
+// Boards where `robot_count` robots have to stand on their own targets at
+// once: red on the goal, the others on corner tiles picked at random for
+// each board.
+fn solve_together(map_path: &str, goal: Position, heuristic_name: &str, solver_name: &str, limits: &Limits, robot_count: usize, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 20);
+    let tiles: Vec<Position> = MapAnalysis::new(&empty_board).corner_tiles().into_iter().filter(|tile| *tile != goal).collect();
+    let mut rng = StdRng::seed_from_u64(BENCHMARK_SEED);
+    let mut total_length = 0;
+    let mut solved = 0;
+    let mut total_stats = SolveStats::default();
+    for board in &boards {
+        let mut targets = vec![(Colour::RED, goal)];
+        targets.extend(COLOURS[1..].iter().copied().zip(tiles.choose_multiple(&mut rng, robot_count.saturating_sub(1)).cloned()));
+        let together = match Together::new(&targets[..robot_count.min(targets.len())]) {
+            Ok(together) => together,
+            Err(error) => {
+                println!("Can't place robots together: {}", error);
+                return;
+            },
+        };
+        let heuristic: Box<dyn Heuristic> = match heuristic_name {
+            "zero" => Box::new(Zero),
+            "flood" => {
+                let distances = together.targets().iter()
+                    .map(|(_, target)| distance_cache.get(&EmptyBoard::with_map(*map, *target), *target))
+                    .collect();
+                Box::new(TogetherDistances::new(&together, distances))
+            },
+            _ => {
+                println!("Robots together take the zero or flood heuristic, not {}", heuristic_name);
+                return;
+            },
+        };
+        let solver = match make_solver(solver_name, &*heuristic) {
+            Some(solver) => solver,
+            None => {
+                println!("Unknown solver {}", solver_name);
+                return;
+            },
+        };
+        let goal_spec = GoalSpec::Together(together);
+        let result = solver.solve(board, &goal_spec, limits);
+        total_stats.add(&result.stats);
+        let placed: Vec<String> = together.targets().iter().map(|(colour, target)| format!("{:?} on {}", colour, target)).collect();
+        let solution = match result.solution {
+            Some(solution) => solution,
+            None => {
+                println!("{}: no solution, {:?}", placed.join(", "), result.stopped);
+                continue;
+            },
+        };
+        if !solution.verify(board, &goal_spec, &limits.rules) {
+            println!("Invalid solution: {:?}", solution.moves);
+        }
+        let moves: Vec<String> = solution.moves.iter().map(|step| step.to_string()).collect();
+        println!("{}; {}: {}", solution.length(), placed.join(", "), moves.join(", "));
+        total_length += solution.length() as i64;
+        solved += 1;
+    }
+    println!("Average length {} over {} of {} boards", total_length as f64 / solved.max(1) as f64, solved, boards.len());
+    print_search_stats(&total_stats, boards.len() as u64);
+}
+
+// Whole games with the robots left where each round ends: `game_count`
+// games with their targets in a random order, seeded from `--seed`, or the
+// one game `--script` lists. Logs every round, then how long solutions run
+// on average by round.
+fn play_games(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, game_count: usize, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let analysis = MapAnalysis::new(&empty_board);
+    if make_heuristic(heuristic_name, &empty_board, goal, distance_cache).is_none() {
+        println!("Unknown heuristic {}", heuristic_name);
+        return;
+    }
+    let script = match flag_text("--script") {
+        Some(path) => match fs::read_to_string(&path).map_err(|error| error.to_string()).and_then(|text| parse_script(&text, &analysis)) {
+            Ok(targets) => Some(targets),
+            Err(error) => {
+                println!("Can't read script {}: {}", path, error);
+                return;
+            },
+        },
+        None => None,
+    };
+    let first_seed = flag_value("--seed").map_or(BENCHMARK_SEED, |seed| seed as u64);
+    let game_count = if script.is_some() { 1 } else { game_count };
+    let mut round_lengths: Vec<(i64, usize)> = Vec::new();
+    let mut total_moves = 0;
+    let mut rounds_played = 0;
+    let mut total_stats = SolveStats::default();
+    for seed in first_seed..first_seed + game_count as u64 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let robots = analysis.random_placement(&mut rng, 4);
+        let board = Board::with_map(map.clone(), robots[0], robots[1], robots[2], robots[3], goal);
+        let targets = script.clone().unwrap_or_else(|| shuffled_targets(&analysis.corner_tiles(), seed));
+        let game = Game::play(&board, &targets, limits, &mut |target| {
+            make_heuristic(heuristic_name, &EmptyBoard::with_map(*map, target), target, distance_cache).expect("known heuristic")
+        });
+        println!("Game {}, robots at {:?}", seed, robots);
+        for (i, round) in game.rounds.iter().enumerate() {
+            total_stats.add(&round.stats);
+            let spec = GoalSpec::Robot(round.target.colour);
+            match &round.solution {
+                Some(solution) => {
+                    let start = Board::with_map(map.clone(), round.robots[0], round.robots[1], round.robots[2], round.robots[3], round.target.position);
+                    if !solution.verify(&start, &spec, &limits.rules) {
+                        println!("Invalid solution: {:?}", solution.moves);
+                    }
+                    let moves: Vec<String> = solution.moves.iter().map(|step| step.to_string()).collect();
+                    println!("Round {}, {:?} to {}: {} moves: {}", i + 1, round.target.colour, round.target.position, solution.length(), moves.join(", "));
+                    if round_lengths.len() <= i {
+                        round_lengths.push((0, 0));
+                    }
+                    round_lengths[i].0 += solution.length() as i64;
+                    round_lengths[i].1 += 1;
+                },
+                None => println!("Round {}, {:?} to {}: no solution, {:?}", i + 1, round.target.colour, round.target.position, round.stopped),
+            }
+        }
+        println!("Game {}: {} moves over {} rounds", seed, game.total_moves(), game.rounds.len());
+        total_moves += game.total_moves();
+        rounds_played += game.rounds.len() as u64;
+    }
+    for (i, (length, solved)) in round_lengths.iter().enumerate() {
+        println!("Round {}: average length {} over {} games", i + 1, *length as f64 / (*solved).max(1) as f64, solved);
+    }
+    println!("Average game: {} moves", total_moves as f64 / game_count as f64);
+    print_search_stats(&total_stats, rounds_played);
+}
+
+// Every target in a corner for every colour on the first benchmark boards,
+// all in one batch and then one solve at a time, to check the batch gets the
+// same lengths and see how much time it saves.
+fn solve_all_targets(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, sweep_nodes: u64, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 20);
+    let targets = MapAnalysis::new(&empty_board).corner_tiles();
+    if make_heuristic(heuristic_name, &empty_board, goal, distance_cache).is_none() {
+        println!("Unknown heuristic {}", heuristic_name);
+        return;
+    }
+    let batch = BatchSolver::new(sweep_nodes);
+    let mut batch_stats = SolveStats::default();
+    let mut separate_stats = SolveStats::default();
+    let mut from_sweep = 0;
+    let mut mismatches = 0;
+    for (i, board) in boards.iter().enumerate() {
+        let table = batch.solve(board, &targets, limits, &mut |target| {
+            let target_board = EmptyBoard::with_map(*map, target);
+            make_heuristic(heuristic_name, &target_board, target, distance_cache).expect("known heuristic")
+        });
+        batch_stats.add(&table.stats);
+        from_sweep += table.from_sweep;
+        if i == 0 {
+            println!("Robots at {:?}", board.get_robots());
+            for entries in table.entries.chunks(COLOURS.len()) {
+                let lengths: Vec<String> = entries.iter().map(|entry| match &entry.solution {
+                    Some(solution) => solution.length().to_string(),
+                    None => "-".to_string(),
+                }).collect();
+                println!("{:?}: {}", entries[0].target, lengths.join(", "));
+            }
+        }
+        for entry in &table.entries {
+            let target_board = board.with_goal(entry.target);
+            let goal_spec = GoalSpec::Robot(entry.colour);
+            if let Some(solution) = &entry.solution {
+                if !solution.verify(&target_board, &goal_spec, &limits.rules) {
+                    println!("Invalid solution: {:?}", solution.moves);
+                }
+            }
+            let heuristic = make_heuristic(heuristic_name, &EmptyBoard::with_map(*map, entry.target), entry.target, distance_cache).expect("known heuristic");
+            let result = AStar::new(&*heuristic).solve(&target_board, &goal_spec, limits);
+            separate_stats.add(&result.stats);
+            let batch_length = entry.solution.as_ref().map(|solution| solution.length());
+            let separate_length = result.solution.as_ref().map(|solution| solution.length());
+            if batch_length != separate_length && entry.stopped.is_none() && result.stopped.is_none() {
+                mismatches += 1;
+                println!("{:?}, {:?} to {:?}: {:?} moves in the batch, {:?} alone", board.get_robots(), entry.colour, entry.target, batch_length, separate_length);
+            }
+        }
+    }
+    let pairs = boards.len() * targets.len() * COLOURS.len();
+    println!("{} targets, {} of {} pairs settled by the sweep, {} lengths differ", targets.len(), from_sweep, pairs, mismatches);
+    println!("In one batch: {:?} in all, {} expanded", batch_stats.wall_time, batch_stats.nodes_expanded);
+    println!("One at a time: {:?} in all, {} expanded", separate_stats.wall_time, separate_stats.nodes_expanded);
+}
+
+// Solve lengths under each set of rules, on the benchmark boards and on the
+// same boards with the red robot moved onto the goal, and where they differ
+// from solving with no rules at all.
+fn compare_rules(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 1000);
+    let on_goal: Vec<Board> = boards.iter().map(|board| {
+        let mut robots = board.get_robots();
+        if let Some(helper) = robots.iter().position(|robot| *robot == goal) {
+            robots[helper] = robots[0];
+        }
+        robots[0] = goal;
+        Board::with_map(map.clone(), robots[0], robots[1], robots[2], robots[3], goal)
+    }).collect();
+    let heuristic = match make_heuristic(heuristic_name, &empty_board, goal, distance_cache) {
+        Some(heuristic) => heuristic,
+        None => {
+            println!("Unknown heuristic {}", heuristic_name);
+            return;
+        },
+    };
+    let configs: Vec<(&str, Limits)> = [
+        ("no rules", Rules::default()),
+        ("official", Rules::official()),
+        ("official with ricochet", Rules { ricochet: true, ..Rules::official() }),
+    ].iter().map(|(name, rules)| (*name, Limits { rules: *rules, ..limits.clone() })).collect();
+    let solver = AStar::new(&*heuristic);
+    compare_lengths(&solver, &[("random boards", &boards), ("red on the goal", &on_goal)], &configs);
+}
+
+// How much each helper matters: solve lengths with each of them held still,
+// with red alone, and with only so many helper moves allowed.
+fn compare_constraints(map_path: &str, goal: Position, heuristic_name: &str, limits: &Limits, distance_cache: &mut DistanceCache) {
+    let map = Rc::new(load_map(map_path));
+    let empty_board = EmptyBoard::with_map(*map, goal);
+    let boards = benchmark_boards(&map, &empty_board, goal, 1000);
+    let heuristic = match make_heuristic(heuristic_name, &empty_board, goal, distance_cache) {
+        Some(heuristic) => heuristic,
+        None => {
+            println!("Unknown heuristic {}", heuristic_name);
+            return;
+        },
+    };
+    let constrained = |constraints: Constraints| Limits { constraints, ..limits.clone() };
+    let configs = [
+        ("no constraints", constrained(Constraints::default())),
+        ("without green", constrained(Constraints::only(&[Colour::RED, Colour::BLUE, Colour::YELLOW]))),
+        ("without blue", constrained(Constraints::only(&[Colour::RED, Colour::GREEN, Colour::YELLOW]))),
+        ("without yellow", constrained(Constraints::only(&[Colour::RED, Colour::GREEN, Colour::BLUE]))),
+        ("red alone", constrained(Constraints::only(&[Colour::RED]))),
+        ("one helper move", constrained(Constraints { helper_moves: Some(1), ..Constraints::default() })),
+        ("two helper moves", constrained(Constraints { helper_moves: Some(2), ..Constraints::default() })),
+    ];
+    let solver = AStar::new(&*heuristic);
+    compare_lengths(&solver, &[("random boards", &boards)], &configs);
+}
+
+// Solve lengths of each set of boards under each of `configs`, and which
+// differ from the first.
+fn compare_lengths(solver: &dyn Solver, sets: &[(&str, &[Board])], configs: &[(&str, Limits)]) {
+    let goal_spec = GoalSpec::Robot(Colour::RED);
+    for (set_name, set) in sets {
+        let mut baseline: Vec<Option<i16>> = Vec::new();
+        for (config_name, limits) in configs {
+            let mut total_length = 0;
+            let mut solved = 0;
+            let mut unsolvable = 0;
+            let mut differences = 0;
+            for (i, board) in set.iter().enumerate() {
+                let result = solver.solve(board, &goal_spec, limits);
+                let length = result.solution.as_ref().map(|solution| solution.length());
+                match (&result.solution, result.stopped) {
+                    (Some(solution), _) => {
+                        if !solution.verify(board, &goal_spec, &limits.rules) {
+                            println!("Invalid solution: {:?}", solution.moves);
+                        }
+                        total_length += solution.length() as i64;
+                        solved += 1;
+                    },
+                    (None, None) => unsolvable += 1,
+                    (None, Some(stopped)) => println!("{:?}: stopped, {:?}", board.get_robots(), stopped),
+                }
+                if baseline.len() <= i {
+                    baseline.push(length);
+                } else if baseline[i] != length {
+                    differences += 1;
+                    if differences <= 3 {
+                        let moves: Vec<String> = result.solution.iter().flat_map(|solution| &solution.moves).map(|step| step.to_string()).collect();
+                        println!("{:?}: {:?} moves, was {:?}: {}", board.get_robots(), length, baseline[i], moves.join(", "));
+                    }
+                }
+            }
+            println!(
+                "{}, {}: average length {} over {} solved, {} unsolvable, {} of {} boards differ from {}",
+                set_name, config_name, total_length as f64 / solved.max(1) as f64, solved, unsolvable,
+                differences, set.len(), configs[0].0,
+            );
+        }
+    }
+}
+
+// Per solve averages of search totals over `solve_count` solves.
+fn print_search_stats(stats: &SolveStats, solve_count: u64) {
+    if solve_count == 0 {
+        return;
+    }
+    let count = solve_count as f64;
+    println!(
+        "Per solve: {} expanded, {} generated, {} duplicates, {} heuristic calls, {} cache hits, {}ms",
+        stats.nodes_expanded as f64 / count, stats.nodes_generated as f64 / count,
+        stats.duplicates_skipped as f64 / count, stats.heuristic_calls as f64 / count,
+        stats.cache_hits as f64 / count, stats.wall_time.as_micros() as f64 / count / 1000.0,
+    );
+    println!("Peak open list: {}", stats.peak_open);
+}
+
+fn make_heuristic(name: &str, empty_board: &EmptyBoard, goal: Position, distance_cache: &mut DistanceCache) -> Option<Box<dyn Heuristic>> {
+    match name {
+        "zero" => Some(Box::new(Zero)),
+        "flood" => Some(Box::new(FloodFill::new(distance_cache.get(empty_board, goal)))),
+        "blocker" => Some(Box::new(BlockerAware::new(distance_cache.get(empty_board, goal), Blockers::new(empty_board, goal)))),
+        "pattern" => Some(Box::new(PatternDatabase::new(empty_board, goal))),
+        _ => None,
+    }
+}
+
+fn make_solver<'a>(name: &str, heuristic: &'a dyn Heuristic) -> Option<Box<dyn Solver + 'a>> {
+    match name {
+        "bfs" => Some(Box::new(BreadthFirst)),
+        "astar" => Some(Box::new(AStar::new(heuristic))),
+        "ida" => Some(Box::new(IdaStar::new(heuristic))),
+        "anytime" => Some(Box::new(Anytime::new(heuristic))),
+        "beam" => Some(Box::new(Beam::new(heuristic, 1000))),
+        _ => None,
+    }
+}
+
+fn make_pruning(name: &str) -> Option<Pruning> {
+    match name {
+        "all" => Some(Pruning::default()),
+        "none" => Some(Pruning::none()),
+        "reversals" => Some(Pruning { reversals: true, commuting: false }),
+        "commuting" => Some(Pruning { reversals: false, commuting: true }),
+        _ => None,
+    }
 }
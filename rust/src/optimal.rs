@@ -0,0 +1,240 @@
+use std::time::Instant;
+
+use hashbrown::HashMap;
+use hashbrown::HashSet;
+
+use crate::bitboard::Occupancy;
+use crate::bitboard::Walls;
+use crate::board::Board;
+use crate::board::Direction;
+use crate::board::Position;
+use crate::colour_to_int;
+use crate::goal::GoalSpec;
+use crate::heuristic::Heuristic;
+use crate::pruning::Arrival;
+use crate::solver::AStar;
+use crate::solver::Limits;
+use crate::solver::Move;
+use crate::solver::Solution;
+use crate::solver::SolveStats;
+use crate::solver::StopReason;
+use crate::solver::Watch;
+use crate::solver::neighbours;
+use crate::solver::start_state;
+use crate::state::State;
+
+// Every shortest solution of a board, up to a cap. Solutions that only
+// differ in the order of moves that commute count once.
+pub struct OptimalSolutions {
+    // Length of the shortest solutions, if there are any.
+    pub length: Option<i16>,
+    // One of each set of solutions that are the same up to commuting moves,
+    // with the moves in canonical order.
+    pub solutions: Vec<Solution>,
+    // Set if the list may be missing some.
+    pub stopped: Option<StopReason>,
+    pub stats: SolveStats,
+}
+
+impl OptimalSolutions {
+    // Whether the board has exactly one shortest solution.
+    pub fn is_unique(&self) -> bool {
+        self.stopped.is_none() && self.solutions.len() == 1
+    }
+}
+
+// Finds the shortest length with A*, then every path of that length by depth
+// first search, cutting branches whose estimate runs past it.
+pub struct AllOptimal<'a> {
+    heuristic: &'a dyn Heuristic,
+    // Most distinct solutions to collect.
+    cap: usize,
+}
+
+// What the depth first search threads through its recursion.
+struct Collect<'a> {
+    walls: &'a Walls,
+    target: Position,
+    goal: &'a GoalSpec,
+    limits: &'a Limits,
+    start: State,
+    length: i16,
+    path: Vec<Move>,
+    // Nodes with no solution within the moves left, and how many were left.
+    dead_ends: HashMap<(u64, u16), i16>,
+    canonical_solutions: HashSet<Vec<u8>>,
+    solutions: Vec<Solution>,
+    stats: SolveStats,
+    watch: Watch<'a>,
+    stopped: Option<StopReason>,
+}
+
+impl<'a> AllOptimal<'a> {
+    pub fn new(heuristic: &'a dyn Heuristic, cap: usize) -> AllOptimal<'a> {
+        AllOptimal { heuristic, cap }
+    }
+
+    pub fn solve(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> OptimalSolutions {
+        let start_time = Instant::now();
+        let cache_hits_before = self.heuristic.cache_hits();
+        let mut result = self.search(board, goal, limits);
+        result.stats.wall_time = start_time.elapsed();
+        result.stats.cache_hits = self.heuristic.cache_hits() - cache_hits_before;
+        result
+    }
+
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> OptimalSolutions {
+        let mut watch = Watch::new(limits);
+        let shortest = AStar::new(self.heuristic).search_in(board, goal, limits, &mut watch, SolveStats::default());
+        let length = match shortest.solution {
+            Some(solution) => solution.length(),
+            None => return OptimalSolutions { length: None, solutions: Vec::new(), stopped: shortest.stopped, stats: shortest.stats },
+        };
+        let walls = Walls::new(&board.empty_board());
+        let mut collect = Collect {
+            walls: &walls,
+            target: board.get_goal(),
+            goal,
+            limits,
+            start: board.state(),
+            length,
+            path: Vec::new(),
+            dead_ends: HashMap::new(),
+            canonical_solutions: HashSet::new(),
+            solutions: Vec::new(),
+            stats: shortest.stats,
+            watch,
+            stopped: None,
+        };
+        self.collect(&mut collect, start_state(board, goal, limits), None, 0);
+        OptimalSolutions { length: Some(length), solutions: collect.solutions, stopped: collect.stopped, stats: collect.stats }
+    }
+
+    // Collects the solutions through `state`, and whether there were any.
+    fn collect(&self, collect: &mut Collect, state: State, arrival: Option<Arrival>, distance: i16) -> bool {
+        if distance == collect.length {
+            if !collect.goal.is_solved(collect.target, state) {
+                return false;
+            }
+            let moves = canonical_order(collect.walls, collect.start, &collect.path);
+            if collect.canonical_solutions.insert(moves.iter().map(|step| move_code(*step)).collect()) {
+                collect.solutions.push(Solution { moves });
+                if collect.solutions.len() >= self.cap {
+                    collect.stopped = Some(StopReason::SolutionCap);
+                }
+            }
+            return true;
+        }
+        collect.stats.heuristic_calls += 1;
+        if distance + collect.goal.estimate(self.heuristic, state) > collect.length {
+            return false;
+        }
+        let remaining = collect.length - distance;
+        let key = node_key(state, arrival);
+        if collect.dead_ends.get(&key).is_some_and(|dead| *dead >= remaining) {
+            collect.stats.duplicates_skipped += 1;
+            return false;
+        }
+        if let Some(stopped) = collect.watch.check(&collect.stats, collect.length) {
+            collect.stopped = Some(stopped);
+            return false;
+        }
+        collect.stats.nodes_expanded += 1;
+        collect.stats.peak_open = collect.stats.peak_open.max(distance as u64 + 1);
+
+        let mut found = false;
+        for (step, neighbour) in neighbours(collect.walls, state, arrival, collect.limits, collect.goal, &mut collect.stats) {
+            collect.stats.nodes_generated += 1;
+            collect.path.push(step);
+            let neighbour_arrival = Arrival { step, from: state.robot(step.colour) };
+            found |= self.collect(collect, neighbour, Some(neighbour_arrival), distance + 1);
+            collect.path.pop();
+            if collect.stopped.is_some() {
+                return found;
+            }
+        }
+        if !found {
+            collect.dead_ends.insert(key, remaining);
+        }
+        found
+    }
+}
+
+// Which moves pruning allows next depends on the last one, so a node is the
+// board and the move that reached it.
+fn node_key(state: State, arrival: Option<Arrival>) -> (u64, u16) {
+    match arrival {
+        Some(arrival) => {
+            let from = arrival.from.x as u16 | (arrival.from.y as u16) << 4;
+            (state.key(), from | (move_code(arrival.step) as u16) << 8 | 1 << 12)
+        },
+        None => (state.key(), 0),
+    }
+}
+
+// The first of the orders `moves` can be played in, swapping neighbouring
+// moves that commute: at each step, the lowest move that can be swapped
+// forward past everything before it.
+pub fn canonical_order(walls: &Walls, start: State, moves: &[Move]) -> Vec<Move> {
+    let mut state = start;
+    let mut rest = moves.to_vec();
+    let mut ordered = Vec::with_capacity(moves.len());
+    while !rest.is_empty() {
+        let mut best = rest.clone();
+        for index in 1..rest.len() {
+            if let Some(candidate) = swapped_to_front(walls, state, &rest, index) {
+                if move_code(candidate[0]) < move_code(best[0]) {
+                    best = candidate;
+                }
+            }
+        }
+        state = play(walls, state, best[0]);
+        ordered.push(best.remove(0));
+        rest = best;
+    }
+    ordered
+}
+
+// `moves` with the one at `index` swapped forward to the front, if it
+// commutes with each move it passes.
+fn swapped_to_front(walls: &Walls, start: State, moves: &[Move], index: usize) -> Option<Vec<Move>> {
+    let mut moves = moves.to_vec();
+    for position in (0..index).rev() {
+        let before = moves[..position].iter().fold(start, |state, step| play(walls, state, *step));
+        if !commute(walls, before, moves[position], moves[position + 1]) {
+            return None;
+        }
+        moves.swap(position, position + 1);
+    }
+    Some(moves)
+}
+
+// Whether `first` then `second` reaches the same board as the other way
+// round, with each robot sliding in both.
+fn commute(walls: &Walls, state: State, first: Move, second: Move) -> bool {
+    if colour_to_int(first.colour) == colour_to_int(second.colour) {
+        return false;
+    }
+    let first_then_second = play(walls, play(walls, state, first), second);
+    let second_then_first = play(walls, play(walls, state, second), first);
+    let after_second = play(walls, state, second);
+    first_then_second == second_then_first
+        && after_second.robot(second.colour) != state.robot(second.colour)
+        && second_then_first.robot(first.colour) != after_second.robot(first.colour)
+}
+
+fn play(walls: &Walls, state: State, step: Move) -> State {
+    let end = walls.slide(&Occupancy::new(&state.robots()), state.robot(step.colour), step.direction);
+    state.with_robot(step.colour, end)
+}
+
+// Orders moves by colour, then direction.
+fn move_code(step: Move) -> u8 {
+    let direction = match step.direction {
+        Direction::UP => 0,
+        Direction::RIGHT => 1,
+        Direction::DOWN => 2,
+        Direction::LEFT => 3,
+    };
+    colour_to_int(step.colour) as u8 * 4 + direction
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use crate::board::Colour;
+use crate::board::Direction;
+use crate::board::DIRECTIONS;
+use crate::board::EmptyBoard;
+use crate::board::Position;
+use crate::heuristic::Heuristic;
+use crate::state::State;
+
+pub const UNREACHABLE: u8 = u8::MAX;
+
+// Distances for the sub-problem of the target robot and one helper, for
+// every pair of tiles they can stand on.
+//
+// Robots other than the pair are left out, and since they could have been
+// in the way anywhere, both robots may stop on any tile a slide passes. The
+// one exception is the goal: the target only counts as arriving there when
+// a wall or the helper stops it. So any solution of the full board where
+// the last move was stopped by a wall or by helper `h` gives a solution of
+// the (target, h) sub-problem that's no longer, moves of the other robots
+// dropped. That makes the smallest value over the helpers a lower bound; the
+// largest isn't one, as a helper that doesn't take part in the solution can
+// be as far away from the goal as it likes.
+pub struct PatternDatabase {
+    goal: Position,
+    distances: Vec<u8>,
+}
+
+impl PatternDatabase {
+    pub fn new(board: &EmptyBoard, goal: Position) -> PatternDatabase {
+        let mut distances = vec![UNREACHABLE; 256 * 256];
+        let mut queue = VecDeque::new();
+
+        // Pairs one move away: the target slides onto the goal and stops.
+        for direction in DIRECTIONS.iter() {
+            let stopped_by_wall = !board.can_move(goal, *direction);
+            for helper in tiles() {
+                if helper == goal || (!stopped_by_wall && helper != board.get_tile(goal).get_adjacent_position(*direction)) {
+                    continue;
+                }
+                for target in slide_origins(board, goal, *direction, helper) {
+                    let index = pair_index(target, helper);
+                    if distances[index] == UNREACHABLE {
+                        distances[index] = 1;
+                        queue.push_back((target, helper));
+                    }
+                }
+            }
+        }
+
+        // Then breadth first backwards through moves of either robot.
+        while let Some((target, helper)) = queue.pop_front() {
+            let distance = distances[pair_index(target, helper)] + 1;
+            for direction in DIRECTIONS.iter() {
+                if target != goal {
+                    for from in slide_origins(board, target, *direction, helper) {
+                        let index = pair_index(from, helper);
+                        if from != goal && distances[index] == UNREACHABLE {
+                            distances[index] = distance;
+                            queue.push_back((from, helper));
+                        }
+                    }
+                }
+                for from in slide_origins(board, helper, *direction, target) {
+                    let index = pair_index(target, from);
+                    if distances[index] == UNREACHABLE {
+                        distances[index] = distance;
+                        queue.push_back((target, from));
+                    }
+                }
+            }
+        }
+
+        PatternDatabase {
+            goal,
+            distances,
+        }
+    }
+
+    pub fn get(&self, target: Position, helper: Position) -> u8 {
+        if target == self.goal {
+            return 0;
+        }
+        self.distances[pair_index(target, helper)]
+    }
+}
+
+// The closest any one helper gets, in the target-plus-one-helper sub-problem.
+// At least as large as the flood fill and blocker-aware estimates.
+impl Heuristic for PatternDatabase {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16 {
+        let target = state.robot(target_colour);
+        let mut best = UNREACHABLE;
+        for robot in state.robots().iter() {
+            if *robot != target {
+                best = best.min(self.get(target, *robot));
+            }
+        }
+        best as i16
+    }
+}
+
+fn pair_index(target: Position, helper: Position) -> usize {
+    (target.x as usize + 16 * target.y as usize) * 256 + helper.x as usize + 16 * helper.y as usize
+}
+
+fn tiles() -> impl Iterator<Item = Position> {
+    (0..16).flat_map(|y| (0..16).map(move |x| Position { x, y }))
+}
+
+// Tiles a robot can slide in `direction` from and pass through `to`, with
+// `other` in the way.
+fn slide_origins(board: &EmptyBoard, to: Position, direction: Direction, other: Position) -> Vec<Position> {
+    let mut origins = Vec::new();
+    let back = direction.opposite();
+    let mut position = to;
+    while board.can_move(position, back) {
+        let next = board.get_tile(position).get_adjacent_position(back);
+        if next == other || !board.can_move(next, direction) {
+            break;
+        }
+        origins.push(next);
+        position = next;
+    }
+    origins
+}
@@ -0,0 +1,121 @@
+use crate::bitboard::Occupancy;
+use crate::bitboard::Walls;
+use crate::board::Colour;
+use crate::board::Direction;
+use crate::board::Position;
+use crate::colour_to_int;
+use crate::solver::Move;
+use crate::state::State;
+
+// The move a search node was reached by, and where that robot came from.
+#[derive(Debug, Copy, Clone)]
+pub struct Arrival {
+    pub step: Move,
+    pub from: Position,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PruneRule {
+    Reversal,
+    Commuting,
+}
+
+// Which moves a search skips, given the move that led to the node.
+//
+// Reversal: the robot that just moved slides straight back onto the tile it
+// came from. That's the previous board again, never part of a shortest path.
+//
+// Commuting: a robot starting on a lower tile index than the last move did
+// moves after it, and the two moves commute, each one sliding the same way
+// whether or not the other was played first. The same two moves in the other
+// order reach the same board in the same number of moves, and that order
+// isn't pruned. Ordering by tile rather than colour keeps the rule the same
+// when the helpers' colours are swapped, which the canonical visited key
+// relies on.
+//
+// Neither loses optimality, including with a visited set that keeps only the
+// first way a board was reached, so only that node's last move is known. If
+// the last move of a shortest path to a board is pruned as commuting, the
+// swapped pair is another shortest path to it, whose last move starts on a
+// strictly higher tile and comes from a board as far from the start. There
+// are only so many tiles, so some shortest path always gets through.
+#[derive(Debug, Copy, Clone)]
+pub struct Pruning {
+    pub reversals: bool,
+    pub commuting: bool,
+}
+
+impl Default for Pruning {
+    fn default() -> Pruning {
+        Pruning {
+            reversals: true,
+            commuting: true,
+        }
+    }
+}
+
+impl Pruning {
+    pub fn none() -> Pruning {
+        Pruning {
+            reversals: false,
+            commuting: false,
+        }
+    }
+
+    // Whether the robot of `colour` sliding in `direction` from `from` to `to`
+    // in `state` gets pruned, and by which rule.
+    pub fn rule_for(
+        &self,
+        walls: &Walls,
+        state: State,
+        arrival: Option<Arrival>,
+        colour: Colour,
+        direction: Direction,
+        to: Position,
+    ) -> Option<PruneRule> {
+        let last = arrival?;
+        if colour_to_int(colour) == colour_to_int(last.step.colour) {
+            if self.reversals && to == last.from {
+                return Some(PruneRule::Reversal);
+            }
+            return None;
+        }
+        let from = state.robot(colour);
+        if self.commuting && tile_index(from) < tile_index(last.from) && commutes(walls, state, last, colour, direction, to) {
+            return Some(PruneRule::Commuting);
+        }
+        None
+    }
+}
+
+// Whether the move of `colour` from its place in `state` to `to` would have
+// slid the same before the last move, and the last move the same after it.
+fn commutes(walls: &Walls, state: State, last: Arrival, colour: Colour, direction: Direction, to: Position) -> bool {
+    let from = state.robot(colour);
+    let last_to = state.robot(last.step.colour);
+    // Moves along lines that neither robot's ends are on can't get in each
+    // other's way.
+    if !shares_line(from, direction, last.from) && !shares_line(from, direction, last_to)
+        && !shares_line(last.from, last.step.direction, from) && !shares_line(last.from, last.step.direction, to) {
+        return true;
+    }
+    let before = state.with_robot(last.step.colour, last.from);
+    if walls.slide(&Occupancy::new(&before.robots()), from, direction) != to {
+        return false;
+    }
+    let swapped = before.with_robot(colour, to);
+    walls.slide(&Occupancy::new(&swapped.robots()), last.from, last.step.direction) == last_to
+}
+
+// Whether `position` is on the row or column a slide from `from` in
+// `direction` runs along.
+fn shares_line(from: Position, direction: Direction, position: Position) -> bool {
+    match direction {
+        Direction::UP | Direction::DOWN => position.x == from.x,
+        Direction::LEFT | Direction::RIGHT => position.y == from.y,
+    }
+}
+
+fn tile_index(position: Position) -> u8 {
+    position.x as u8 + 16 * position.y as u8
+}
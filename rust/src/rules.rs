@@ -0,0 +1,153 @@
+use crate::board::Colour;
+use crate::board::Direction;
+use crate::board::Position;
+use crate::colour_to_int;
+use crate::solver::Move;
+use crate::state::State;
+
+// Bits of a state's aux progress the rules use, the lowest five.
+// The target robot started on the goal and hasn't moved off it yet.
+const LEAVE_PENDING: u32 = 1;
+// The target robot hasn't turned onto the other axis yet.
+const TURN_PENDING: u32 = 1 << 1;
+// The target robot has moved, along the axis in the next bit.
+const TARGET_MOVED: u32 = 1 << 2;
+const AXIS_SHIFT: u32 = 3;
+const RULE_BITS: u32 = 0b11111;
+
+// Conditions on how a board gets solved, past the target robot stopping on
+// the goal. None of them apply by default.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Rules {
+    // A target robot that starts on the goal has to leave it and come back,
+    // rather than the board counting as solved in no moves.
+    pub leave_goal_first: bool,
+    // House rule: the target robot has to turn at least once, from sliding
+    // up or down to sliding left or right or the other way round, so a
+    // straight slide onto the goal doesn't count. Sliding back the way it
+    // came isn't a turn: the slide back is as straight as the first.
+    pub ricochet: bool,
+}
+
+impl Rules {
+    // The rules of the board game.
+    pub fn official() -> Rules {
+        Rules {
+            leave_goal_first: true,
+            ricochet: false,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.leave_goal_first || self.ricochet
+    }
+
+    // `state` with the progress the rules track at the start of a solve.
+    pub fn start(&self, state: State, goal: Position, target: Colour) -> State {
+        let mut progress = 0;
+        if self.leave_goal_first && state.robot(target) == goal {
+            progress |= LEAVE_PENDING;
+        }
+        if self.ricochet {
+            progress |= TURN_PENDING;
+        }
+        state.with_aux(state.aux() & !RULE_BITS | progress)
+    }
+
+    // `after`, the board once `step` is played, with the progress of the
+    // board before it moved on. Once nothing is pending no more is kept, so
+    // every way of meeting the rules reaches the same state.
+    pub fn advance(&self, before: State, step: Move, after: State, target: Colour) -> State {
+        let mut progress = before.aux() & RULE_BITS;
+        if progress == 0 || colour_to_int(step.colour) != colour_to_int(target) {
+            return after;
+        }
+        progress &= !LEAVE_PENDING;
+        if progress & TURN_PENDING != 0 {
+            let axis = axis_bit(step.direction);
+            if progress & TARGET_MOVED != 0 && progress >> AXIS_SHIFT & 1 != axis {
+                progress &= !(TURN_PENDING | TARGET_MOVED | 1 << AXIS_SHIFT);
+            } else {
+                progress = progress & !(1 << AXIS_SHIFT) | TARGET_MOVED | axis << AXIS_SHIFT;
+            }
+        }
+        after.with_aux(after.aux() & !RULE_BITS | progress)
+    }
+}
+
+// Whether the rules let `state` count as solved, once the target is home.
+pub fn finish_allowed(state: State) -> bool {
+    state.aux() & (LEAVE_PENDING | TURN_PENDING) == 0
+}
+
+fn axis_bit(direction: Direction) -> u32 {
+    match direction {
+        Direction::UP | Direction::DOWN => 0,
+        Direction::LEFT | Direction::RIGHT => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::board::Board;
+    use crate::board::load_map;
+    use crate::goal::GoalSpec;
+    use crate::solver::BreadthFirst;
+    use crate::solver::Limits;
+    use crate::solver::Solution;
+    use crate::solver::Solver;
+
+    // The goal of map1, walled on its left and top.
+    const GOAL: Position = Position { x: 6, y: 14 };
+
+    fn board_with_red_at(red: Position) -> Board {
+        let map = Rc::new(load_map("maps/map1.txt"));
+        Board::with_map(map, red, Position { x: 2, y: 6 }, Position { x: 12, y: 1 }, Position { x: 8, y: 13 }, GOAL)
+    }
+
+    // The shortest solution under `rules`, checked against replaying it.
+    fn solve(board: &Board, rules: Rules) -> Solution {
+        let limits = Limits { rules, ..Limits::default() };
+        let solution = BreadthFirst.solve(board, &GoalSpec::Robot(Colour::RED), &limits).solution.expect("solvable");
+        assert!(solution.verify(board, &GoalSpec::Robot(Colour::RED), &rules));
+        solution
+    }
+
+    fn red(direction: Direction) -> Move {
+        Move { colour: Colour::RED, direction }
+    }
+
+    #[test]
+    fn red_on_the_goal_has_to_leave_under_official_rules() {
+        let board = board_with_red_at(GOAL);
+        assert_eq!(solve(&board, Rules::default()).length(), 0);
+        assert_eq!(solve(&board, Rules::official()).length(), 2);
+        let stay = Solution { moves: Vec::new() };
+        assert!(stay.verify(&board, &GoalSpec::Robot(Colour::RED), &Rules::default()));
+        assert!(!stay.verify(&board, &GoalSpec::Robot(Colour::RED), &Rules::official()));
+    }
+
+    #[test]
+    fn a_straight_slide_is_no_ricochet() {
+        let board = board_with_red_at(Position { x: 15, y: 14 });
+        let ricochet = Rules { ricochet: true, ..Rules::default() };
+        let straight = Solution { moves: vec![red(Direction::LEFT)] };
+        assert_eq!(solve(&board, Rules::default()).length(), 1);
+        assert!(straight.verify(&board, &GoalSpec::Robot(Colour::RED), &Rules::default()));
+        assert!(!straight.verify(&board, &GoalSpec::Robot(Colour::RED), &ricochet));
+        assert_eq!(solve(&board, ricochet).length(), 3);
+    }
+
+    #[test]
+    fn sliding_back_along_the_same_axis_is_no_turn() {
+        let board = board_with_red_at(GOAL);
+        let ricochet = Rules { ricochet: true, ..Rules::official() };
+        let there_and_back = Solution { moves: vec![red(Direction::RIGHT), red(Direction::LEFT)] };
+        assert!(there_and_back.verify(&board, &GoalSpec::Robot(Colour::RED), &Rules::official()));
+        assert!(!there_and_back.verify(&board, &GoalSpec::Robot(Colour::RED), &ricochet));
+        assert_eq!(solve(&board, ricochet).length(), 4);
+    }
+}
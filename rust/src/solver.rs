@@ -0,0 +1,961 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use hashbrown::HashMap;
+use hashbrown::HashSet;
+
+use crate::bitboard::Occupancy;
+use crate::bitboard::Walls;
+use crate::board::Board;
+use crate::board::Colour;
+use crate::board::COLOURS;
+use crate::board::DIRECTIONS;
+use crate::board::Direction;
+use crate::board::Position;
+use crate::bucket::BucketQueue;
+use crate::constraints::Constraints;
+use crate::colour_to_int;
+use crate::goal::GoalSpec;
+use crate::goal::VisitedKey;
+use crate::heuristic::Heuristic;
+use crate::pruning::Arrival;
+use crate::pruning::PruneRule;
+use crate::pruning::Pruning;
+use crate::rules::Rules;
+use crate::state::State;
+use crate::transposition::DistanceTable;
+use crate::transposition::Replacement;
+use crate::transposition::TableStats;
+use crate::transposition::TranspositionTable;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Move {
+    pub colour: Colour,
+    pub direction: Direction,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?}", self.colour, self.direction)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub moves: Vec<Move>,
+}
+
+impl Solution {
+    pub fn length(&self) -> i16 {
+        self.moves.len() as i16
+    }
+
+    // How many different robots move.
+    pub fn robots_moved(&self) -> usize {
+        let mut moved = [false; 4];
+        for step in &self.moves {
+            moved[colour_to_int(step.colour) as usize] = true;
+        }
+        moved.iter().filter(|moved| **moved).count()
+    }
+
+    pub fn moves_of(&self, colour: Colour) -> usize {
+        self.moves.iter().filter(|step| colour_to_int(step.colour) == colour_to_int(colour)).count()
+    }
+
+    // Every board along the solution, from `board` itself to the last one.
+    pub fn replay(&self, board: &Board) -> Vec<Board> {
+        let mut boards = vec![board.clone()];
+        let mut current = board.clone();
+        for step in &self.moves {
+            current.move_robot(step.colour, step.direction);
+            boards.push(current.clone());
+        }
+        boards
+    }
+
+    // Whether every move actually moves its robot, and the last one leaves
+    // `goal` solved under `rules`.
+    pub fn verify(&self, board: &Board, goal: &GoalSpec, rules: &Rules) -> bool {
+        let boards = self.replay(board);
+        let all_move = self.moves.iter().enumerate().all(|(i, step)| {
+            boards[i].get_robot_by_colour(step.colour) != boards[i + 1].get_robot_by_colour(step.colour)
+        });
+        let end = *self.progress(board, goal, rules).last().expect("replay starts from the board");
+        all_move && goal.is_solved(board.get_goal(), end)
+    }
+
+    // The state before each move and after the last, with the progress
+    // `goal` and `rules` keep.
+    pub fn progress(&self, board: &Board, goal: &GoalSpec, rules: &Rules) -> Vec<State> {
+        let boards = self.replay(board);
+        let target = goal.target_colour();
+        let mut states = vec![rules.start(board.state(), goal.target_position(board.get_goal()), target)];
+        for (i, step) in self.moves.iter().enumerate() {
+            let before = states[i];
+            let after = rules.advance(before, *step, boards[i + 1].state().with_aux(before.aux()), target);
+            states.push(goal.advance(after, step.colour));
+        }
+        states
+    }
+}
+
+// Where a running search has got to.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    // Lowest estimated solution length still open, or the depth reached by
+    // searches with no estimates.
+    pub bound: i16,
+    pub nodes_expanded: u64,
+    pub nodes_generated: u64,
+    pub elapsed: Duration,
+}
+
+// Called as a search goes, each time its bound rises and every
+// `PROGRESS_INTERVAL` boards expanded in between.
+#[derive(Clone)]
+pub struct ProgressCallback(pub Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+// The settings of a solve, past the board and goal: limits on how far it
+// may go and when it stops, how it searches (pruning, visited keys and
+// table, tie-break), and what counts as a solution (rules, constraints).
+#[derive(Debug, Default, Clone)]
+pub struct Limits {
+    // Longest solution to look for.
+    pub max_depth: Option<i16>,
+    // Most boards to expand before giving up.
+    pub max_nodes: Option<u64>,
+    // Longest to search for, from when the solve starts.
+    pub max_time: Option<Duration>,
+    // Stops the search once set, from any thread.
+    pub cancel: Option<Arc<AtomicBool>>,
+    pub progress: Option<ProgressCallback>,
+    // Moves the search may skip.
+    pub pruning: Pruning,
+    pub visited_key: VisitedKey,
+    // Memory for A*'s visited set, which is then a transposition table
+    // that forgets boards once full, rather than a map that keeps them all.
+    pub visited_bytes: Option<usize>,
+    pub replacement: Replacement,
+    // Which of several shortest solutions A* returns.
+    pub tie_break: TieBreak,
+    // Conditions on how the goal has to be reached.
+    pub rules: Rules,
+    // Which robots may move, and how often.
+    pub constraints: Constraints,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum TieBreak {
+    // Whichever the search reaches first.
+    #[default]
+    FirstFound,
+    // The official rule: fewest robots moved, then fewest moves of the
+    // target robot. Past that, whichever the search reaches first, which is
+    // the same every run.
+    FewestRobots,
+}
+
+impl Limits {
+    pub fn allows_depth(&self, depth: i16) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+
+    fn allows_nodes(&self, nodes: u64) -> bool {
+        self.max_nodes.is_none_or(|max_nodes| nodes < max_nodes)
+    }
+
+    // Constraints can treat each helper differently, and then which colour
+    // stands where matters.
+    fn effective_visited_key(&self) -> VisitedKey {
+        if self.constraints.any() { VisitedKey::Exact } else { self.visited_key }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+}
+
+// Boards expanded between looks at the clock and the cancel flag.
+const CHECK_INTERVAL: u64 = 1 << 10;
+const PROGRESS_INTERVAL: u64 = 1 << 16;
+
+// Keeps one solve within its limits, and reports its progress.
+pub struct Watch<'a> {
+    limits: &'a Limits,
+    start: Instant,
+    bound: i16,
+    next_check: u64,
+    next_report: u64,
+}
+
+impl<'a> Watch<'a> {
+    pub fn new(limits: &'a Limits) -> Watch<'a> {
+        Watch {
+            limits,
+            start: Instant::now(),
+            bound: i16::MIN,
+            next_check: 0,
+            next_report: PROGRESS_INTERVAL,
+        }
+    }
+
+    // Why the search has to stop before expanding another board, if it does.
+    pub fn check(&mut self, stats: &SolveStats, bound: i16) -> Option<StopReason> {
+        if !self.limits.allows_nodes(stats.nodes_expanded) {
+            return Some(StopReason::NodeLimit);
+        }
+        if bound > self.bound || stats.nodes_expanded >= self.next_report {
+            self.bound = self.bound.max(bound);
+            self.next_report = stats.nodes_expanded + PROGRESS_INTERVAL;
+            self.report(stats);
+        }
+        if stats.nodes_expanded < self.next_check {
+            return None;
+        }
+        self.next_check = stats.nodes_expanded + CHECK_INTERVAL;
+        if self.limits.is_cancelled() {
+            return Some(StopReason::Cancelled);
+        }
+        if self.limits.max_time.is_some_and(|max_time| self.start.elapsed() >= max_time) {
+            return Some(StopReason::TimeLimit);
+        }
+        None
+    }
+
+    fn report(&self, stats: &SolveStats) {
+        if let Some(ProgressCallback(callback)) = &self.limits.progress {
+            callback(&Progress {
+                bound: self.bound,
+                nodes_expanded: stats.nodes_expanded,
+                nodes_generated: stats.nodes_generated,
+                elapsed: self.start.elapsed(),
+            });
+        }
+    }
+}
+
+// Why a search ended without settling whether a board can be solved.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StopReason {
+    NodeLimit,
+    TimeLimit,
+    Cancelled,
+    // Nothing within the depth limit, there may be something past it.
+    DepthLimit,
+    // The strategy gave up, without a proof there's no solution.
+    Incomplete,
+    // As many solutions were found as were asked for, there may be more.
+    SolutionCap,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SolveStats {
+    pub nodes_expanded: u64,
+    pub nodes_generated: u64,
+    // Generated boards dropped because they'd been reached before at no
+    // greater distance, and open entries dropped for the same reason.
+    pub duplicates_skipped: u64,
+    // Most boards waiting to be expanded at once: the open list for A*, the
+    // queue for BFS, a layer for beam search and the path for IDA*.
+    pub peak_open: u64,
+    pub heuristic_calls: u64,
+    // Estimates answered by a cache of known solution lengths.
+    pub cache_hits: u64,
+    pub wall_time: Duration,
+    // Moves skipped by each `Pruning` rule.
+    pub pruned_reversals: u64,
+    pub pruned_commuting: u64,
+    // Counters of the visited set when it's a transposition table.
+    pub visited_table: TableStats,
+}
+
+impl SolveStats {
+    // Adds the counts of another solve, for totals over several. The peak
+    // open size is the largest of any of them.
+    pub fn add(&mut self, other: &SolveStats) {
+        self.nodes_expanded += other.nodes_expanded;
+        self.nodes_generated += other.nodes_generated;
+        self.duplicates_skipped += other.duplicates_skipped;
+        self.peak_open = self.peak_open.max(other.peak_open);
+        self.heuristic_calls += other.heuristic_calls;
+        self.cache_hits += other.cache_hits;
+        self.wall_time += other.wall_time;
+        self.pruned_reversals += other.pruned_reversals;
+        self.pruned_commuting += other.pruned_commuting;
+        self.visited_table.add(&other.visited_table);
+    }
+}
+
+// A solution with a stop reason is the best an anytime search found before
+// it stopped, and may not be the shortest.
+pub struct SolveResult {
+    pub solution: Option<Solution>,
+    pub stopped: Option<StopReason>,
+    pub stats: SolveStats,
+}
+
+impl SolveResult {
+    fn solved(moves: Vec<Move>, stats: SolveStats) -> SolveResult {
+        SolveResult {
+            solution: Some(Solution { moves }),
+            stopped: None,
+            stats,
+        }
+    }
+
+    fn unsolved(stopped: Option<StopReason>, stats: SolveStats) -> SolveResult {
+        SolveResult {
+            solution: None,
+            stopped,
+            stats,
+        }
+    }
+}
+
+// A search strategy. A result with no solution and no stop reason means the
+// board can't be solved.
+pub trait Solver {
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult;
+
+    // The heuristic the search estimates with, if any.
+    fn heuristic(&self) -> Option<&dyn Heuristic> {
+        None
+    }
+
+    // Searches, and adds the stats the strategy itself doesn't track.
+    fn solve(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult {
+        let start = Instant::now();
+        let cache_hits_before = self.heuristic().map_or(0, |heuristic| heuristic.cache_hits());
+        let mut result = self.search(board, goal, limits);
+        result.stats.wall_time = start.elapsed();
+        if let Some(heuristic) = self.heuristic() {
+            result.stats.cache_hits = heuristic.cache_hits() - cache_hits_before;
+        }
+        result
+    }
+}
+
+// The state a solve of `board` starts from, with the progress its rules
+// track.
+pub fn start_state(board: &Board, goal: &GoalSpec, limits: &Limits) -> State {
+    limits.rules.start(board.state(), goal.target_position(board.get_goal()), goal.target_colour())
+}
+
+// Every state one move away from `state`, with the move that gets there,
+// less the moves the pruning of `limits` skips after `arrival` and those of
+// robots its constraints hold back.
+pub fn neighbours(
+    walls: &Walls,
+    state: State,
+    arrival: Option<Arrival>,
+    limits: &Limits,
+    goal: &GoalSpec,
+    stats: &mut SolveStats,
+) -> Vec<(Move, State)> {
+    let rules = &limits.rules;
+    let constraints = &limits.constraints;
+    let has_rules = rules.any();
+    let constrained = constraints.any();
+    let tracks_progress = goal.tracks_progress();
+    let target = goal.target_colour();
+    // Under rules, or a goal with progress of its own, sliding straight back
+    // gives the same robots with different progress, which can be the way
+    // to meet them.
+    let pruning = if has_rules || tracks_progress { Pruning { reversals: false, ..limits.pruning } } else { limits.pruning };
+    let occupancy = Occupancy::new(&state.robots());
+    let mut neighbours = Vec::with_capacity(16);
+    for colour in COLOURS.iter() {
+        if constrained && !constraints.allows(state, *colour, target) {
+            continue;
+        }
+        let from = state.robot(*colour);
+        for direction in DIRECTIONS.iter() {
+            let end = walls.slide(&occupancy, from, *direction);
+            if end == from {
+                continue;
+            }
+            match pruning.rule_for(walls, state, arrival, *colour, *direction, end) {
+                Some(PruneRule::Reversal) => stats.pruned_reversals += 1,
+                Some(PruneRule::Commuting) => stats.pruned_commuting += 1,
+                None => {
+                    let step = Move { colour: *colour, direction: *direction };
+                    let mut neighbour = state.with_robot(*colour, end);
+                    if has_rules {
+                        neighbour = rules.advance(state, step, neighbour, target);
+                    }
+                    if constrained {
+                        neighbour = constraints.advance(neighbour, *colour, target);
+                    }
+                    if tracks_progress {
+                        neighbour = goal.advance(neighbour, *colour);
+                    }
+                    neighbours.push((step, neighbour));
+                },
+            }
+        }
+    }
+    neighbours
+}
+
+pub const ROOT: usize = usize::MAX;
+
+// Moves of every path a search has taken, each stored once with a link to
+// the step before it.
+pub struct Trail {
+    steps: Vec<(usize, Move)>,
+}
+
+impl Trail {
+    pub fn new() -> Trail {
+        Trail { steps: Vec::new() }
+    }
+
+    pub fn push(&mut self, parent: usize, step: Move) -> usize {
+        self.steps.push((parent, step));
+        self.steps.len() - 1
+    }
+
+    pub fn moves(&self, mut index: usize) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while index != ROOT {
+            let (parent, step) = self.steps[index];
+            moves.push(step);
+            index = parent;
+        }
+        moves.reverse();
+        moves
+    }
+}
+
+// Reference search, optimal and with no heuristic to get wrong.
+pub struct BreadthFirst;
+
+impl Solver for BreadthFirst {
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult {
+        let mut stats = SolveStats::default();
+        let walls = Walls::new(&board.empty_board());
+        let target = board.get_goal();
+        let start = start_state(board, goal, limits);
+        if goal.is_solved(target, start) {
+            return SolveResult::solved(Vec::new(), stats);
+        }
+        let mut trail = Trail::new();
+        let mut queue = VecDeque::new();
+        let mut visited_board_states = HashSet::new();
+        let mut depth_cut = false;
+        let mut watch = Watch::new(limits);
+        let visited_key = limits.effective_visited_key();
+        visited_board_states.insert(goal.visited_key(start, visited_key));
+        queue.push_back((start, 0, ROOT, None));
+
+        while let Some((current, distance, path, arrival)) = queue.pop_front() {
+            if let Some(stopped) = watch.check(&stats, distance) {
+                return SolveResult::unsolved(Some(stopped), stats);
+            }
+            stats.nodes_expanded += 1;
+            if !limits.allows_depth(distance + 1) {
+                depth_cut = true;
+                continue;
+            }
+            for (step, neighbour) in neighbours(&walls, current, arrival, limits, goal, &mut stats) {
+                stats.nodes_generated += 1;
+                if !visited_board_states.insert(goal.visited_key(neighbour, visited_key)) {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                let neighbour_path = trail.push(path, step);
+                if goal.is_solved(target, neighbour) {
+                    return SolveResult::solved(trail.moves(neighbour_path), stats);
+                }
+                let from = current.robot(step.colour);
+                queue.push_back((neighbour, distance + 1, neighbour_path, Some(Arrival { step, from })));
+                stats.peak_open = stats.peak_open.max(queue.len() as u64);
+            }
+        }
+        SolveResult::unsolved(if depth_cut { Some(StopReason::DepthLimit) } else { None }, stats)
+    }
+}
+
+struct PathOption {
+    state: State,
+    distance: i16,
+    path: usize,
+    arrival: Option<Arrival>,
+    // A bit per robot that has moved, and how often the target robot has,
+    // for the tie-break.
+    moved: u8,
+    target_moves: i16,
+}
+
+// Target robot moves the tie-break tells apart, fewer than this.
+const TARGET_MOVE_RANKS: usize = 32;
+
+// Weights are in tenths, so this one counts the estimate as it is.
+const UNWEIGHTED: usize = 10;
+
+// Optimal as long as the heuristic never overestimates.
+pub struct AStar<'a> {
+    heuristic: &'a dyn Heuristic,
+    // Tenths of the estimate added to the distance so far to order boards.
+    // Past `UNWEIGHTED` the search heads for the goal sooner, and the
+    // solution can be up to that many tenths of the shortest.
+    weight: usize,
+    // Skips boards that can't lead to a solution shorter than this.
+    shorter_than: Option<i16>,
+}
+
+impl<'a> AStar<'a> {
+    pub fn new(heuristic: &'a dyn Heuristic) -> AStar<'a> {
+        AStar { heuristic, weight: UNWEIGHTED, shorter_than: None }
+    }
+}
+
+impl<'a> Solver for AStar<'a> {
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult {
+        self.search_in(board, goal, limits, &mut Watch::new(limits), SolveStats::default())
+    }
+
+    fn heuristic(&self) -> Option<&dyn Heuristic> {
+        Some(self.heuristic)
+    }
+}
+
+impl<'a> AStar<'a> {
+    // Searches with the visited set `limits` asks for, adding to `stats`.
+    pub fn search_in(&self, board: &Board, goal: &GoalSpec, limits: &Limits, watch: &mut Watch, stats: SolveStats) -> SolveResult {
+        match limits.visited_bytes {
+            Some(bytes) => {
+                let mut visited_board_states = TranspositionTable::new(bytes, limits.replacement);
+                let mut result = self.search_with(board, goal, limits, watch, stats, &mut visited_board_states);
+                result.stats.visited_table.add(&visited_board_states.stats());
+                result
+            },
+            None => self.search_with(board, goal, limits, watch, stats, &mut HashMap::new()),
+        }
+    }
+
+    fn search_with<T: DistanceTable>(
+        &self,
+        board: &Board,
+        goal: &GoalSpec,
+        limits: &Limits,
+        watch: &mut Watch,
+        mut stats: SolveStats,
+        visited_board_states: &mut T,
+    ) -> SolveResult {
+        let walls = Walls::new(&board.empty_board());
+        let target = board.get_goal();
+        let mut trail = Trail::new();
+        let mut open = BucketQueue::new();
+        let mut depth_cut = false;
+        let tie_break = limits.tie_break == TieBreak::FewestRobots;
+        let target_bit = 1 << colour_to_int(goal.target_colour());
+        // With the tie-break, boards are only the same if the same robots
+        // have moved to reach them, and which ones those are depends on the
+        // helpers' colours.
+        let visited_key = if tie_break { VisitedKey::Exact } else { limits.effective_visited_key() };
+        let node_key = |state: State, moved: u8| goal.visited_key(state, visited_key).key() | (moved as u64) << 32;
+        // Ways to a board are compared by length, then target robot moves.
+        let rank = |distance: i16, target_moves: i16| {
+            if tie_break { distance * TARGET_MOVE_RANKS as i16 + target_moves } else { distance }
+        };
+        let start = start_state(board, goal, limits);
+        visited_board_states.insert(node_key(start, 0), 0);
+        open.push(0, PathOption {
+            state: start,
+            distance: 0,
+            path: ROOT,
+            arrival: None,
+            moved: 0,
+            target_moves: 0,
+        });
+
+        while let Some((priority, option)) = open.pop() {
+            // A better way here was found after this one was queued.
+            let key = node_key(option.state, option.moved);
+            if visited_board_states.get(key).is_some_and(|best| best < rank(option.distance, option.target_moves)) {
+                stats.duplicates_skipped += 1;
+                continue;
+            }
+            if goal.is_solved(target, option.state) {
+                return SolveResult::solved(trail.moves(option.path), stats);
+            }
+            let bound = if tie_break { priority / (5 * TARGET_MOVE_RANKS) } else { priority } / UNWEIGHTED;
+            if let Some(stopped) = watch.check(&stats, bound as i16) {
+                return SolveResult::unsolved(Some(stopped), stats);
+            }
+            stats.nodes_expanded += 1;
+            if !limits.allows_depth(option.distance + 1) {
+                depth_cut = true;
+                continue;
+            }
+
+            for (step, neighbour) in neighbours(&walls, option.state, option.arrival, limits, goal, &mut stats) {
+                stats.nodes_generated += 1;
+                let step_bit = 1 << colour_to_int(step.colour);
+                let (moved, target_moves) = if tie_break {
+                    (option.moved | step_bit, option.target_moves + (step_bit == target_bit) as i16)
+                } else {
+                    (0, 0)
+                };
+                let key = node_key(neighbour, moved);
+                let neighbour_rank = rank(option.distance + 1, target_moves);
+                if visited_board_states.get(key).is_some_and(|best| best <= neighbour_rank) {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                let estimate = goal.estimate(self.heuristic, neighbour);
+                stats.heuristic_calls += 1;
+                if self.shorter_than.is_some_and(|length| option.distance + 1 + estimate >= length) {
+                    continue;
+                }
+                visited_board_states.insert(key, neighbour_rank);
+                let mut priority = (option.distance + 1) as usize * UNWEIGHTED + estimate as usize * self.weight;
+                if tie_break {
+                    // Lower bounds on robots moved and target moves: the
+                    // target still has to move unless it's already home.
+                    let must_move = goal.target_must_move(target, neighbour);
+                    let robots = (moved | if must_move { target_bit } else { 0 }).count_ones() as usize;
+                    let target_moves = (target_moves as usize + must_move as usize).min(TARGET_MOVE_RANKS - 1);
+                    priority = (priority * 5 + robots) * TARGET_MOVE_RANKS + target_moves;
+                }
+                open.push(priority, PathOption {
+                    state: neighbour,
+                    distance: option.distance + 1,
+                    path: trail.push(option.path, step),
+                    arrival: Some(Arrival { step, from: option.state.robot(step.colour) }),
+                    moved,
+                    target_moves,
+                });
+                stats.peak_open = stats.peak_open.max(open.len() as u64);
+            }
+        }
+        SolveResult::unsolved(if depth_cut { Some(StopReason::DepthLimit) } else { None }, stats)
+    }
+}
+
+// A solution an anytime search has found, shorter than any before it.
+#[derive(Debug, Clone)]
+pub struct Improvement {
+    pub solution: Solution,
+    // Whether there's sure to be nothing shorter.
+    pub optimal: bool,
+    // Time since the search started.
+    pub elapsed: Duration,
+}
+
+// Weighted A* with a shrinking weight, each pass only looking for solutions
+// shorter than the last one found. The first solution comes quickly, and the
+// last pass is plain A*, so given the time it ends with a proven shortest.
+pub struct Anytime<'a> {
+    heuristic: &'a dyn Heuristic,
+    // Weights of each pass, in tenths, ending with `UNWEIGHTED`.
+    weights: Vec<usize>,
+}
+
+impl<'a> Anytime<'a> {
+    pub fn new(heuristic: &'a dyn Heuristic) -> Anytime<'a> {
+        Anytime { heuristic, weights: vec![30, 20, 15, 12, UNWEIGHTED] }
+    }
+
+    // Searches until a solution is proven shortest or `limits` stop it,
+    // passing each better solution to `improved` as it's found, and the best
+    // again once a later pass proves it shortest. The result holds the best
+    // solution, and a stop reason if it isn't proven.
+    pub fn solve_improving(
+        &self,
+        board: &Board,
+        goal: &GoalSpec,
+        limits: &Limits,
+        improved: &mut dyn FnMut(&Improvement),
+    ) -> SolveResult {
+        let start = Instant::now();
+        let cache_hits_before = self.heuristic.cache_hits();
+        let mut result = self.search_improving(board, goal, limits, improved);
+        result.stats.wall_time = start.elapsed();
+        result.stats.cache_hits = self.heuristic.cache_hits() - cache_hits_before;
+        result
+    }
+
+    fn search_improving(
+        &self,
+        board: &Board,
+        goal: &GoalSpec,
+        limits: &Limits,
+        improved: &mut dyn FnMut(&Improvement),
+    ) -> SolveResult {
+        let mut watch = Watch::new(limits);
+        let mut stats = SolveStats::default();
+        let mut best: Option<Solution> = None;
+        let lower_bound = goal.estimate(self.heuristic, board.state());
+        for weight in &self.weights {
+            let pass = AStar {
+                heuristic: self.heuristic,
+                weight: *weight,
+                shorter_than: best.as_ref().map(|solution| solution.length()),
+            };
+            let result = pass.search_in(board, goal, limits, &mut watch, stats);
+            stats = result.stats;
+            match (result.solution, result.stopped) {
+                (Some(solution), _) => {
+                    // Plain A* finds a shortest solution, and nothing is
+                    // shorter than the estimate of the start.
+                    let optimal = *weight == UNWEIGHTED || solution.length() <= lower_bound;
+                    improved(&Improvement { solution: solution.clone(), optimal, elapsed: watch.start.elapsed() });
+                    if optimal {
+                        return SolveResult::solved(solution.moves, stats);
+                    }
+                    best = Some(solution);
+                },
+                // Every board that could be on a shorter solution was
+                // searched, so the best so far is the shortest.
+                (None, None) => break,
+                (None, Some(stopped)) => return SolveResult { solution: best, stopped: Some(stopped), stats },
+            }
+        }
+        if let Some(solution) = &best {
+            improved(&Improvement { solution: solution.clone(), optimal: true, elapsed: watch.start.elapsed() });
+        }
+        SolveResult { solution: best, stopped: None, stats }
+    }
+}
+
+impl<'a> Solver for Anytime<'a> {
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult {
+        self.search_improving(board, goal, limits, &mut |_| {})
+    }
+
+    fn heuristic(&self) -> Option<&dyn Heuristic> {
+        Some(self.heuristic)
+    }
+}
+
+enum Deepening {
+    Found,
+    // Smallest estimate past the bound, i16::MAX if there was none.
+    Exceeded(i16),
+    Stopped(StopReason),
+}
+
+// What one IDA* solve threads through its recursion.
+struct Deepen<'a> {
+    walls: &'a Walls,
+    target: Position,
+    goal: &'a GoalSpec,
+    limits: &'a Limits,
+    bound: i16,
+    path: Vec<Move>,
+    path_states: Vec<State>,
+    stats: SolveStats,
+    depth_cut: bool,
+    watch: Watch<'a>,
+}
+
+// Depth first searches with a growing bound on the estimated length. Keeps
+// no visited set, only the boards on the current path, so memory stays flat
+// however long it runs.
+pub struct IdaStar<'a> {
+    heuristic: &'a dyn Heuristic,
+}
+
+impl<'a> IdaStar<'a> {
+    pub fn new(heuristic: &'a dyn Heuristic) -> IdaStar<'a> {
+        IdaStar { heuristic }
+    }
+
+    fn deepen(&self, search: &mut Deepen, state: State, arrival: Option<Arrival>, distance: i16) -> Deepening {
+        let estimate = distance + search.goal.estimate(self.heuristic, state);
+        search.stats.heuristic_calls += 1;
+        search.stats.peak_open = search.stats.peak_open.max(search.path_states.len() as u64);
+        if estimate > search.bound {
+            return Deepening::Exceeded(estimate);
+        }
+        if search.goal.is_solved(search.target, state) {
+            return Deepening::Found;
+        }
+        if let Some(stopped) = search.watch.check(&search.stats, search.bound) {
+            return Deepening::Stopped(stopped);
+        }
+        search.stats.nodes_expanded += 1;
+        if !search.limits.allows_depth(distance + 1) {
+            search.depth_cut = true;
+            return Deepening::Exceeded(i16::MAX);
+        }
+
+        let mut next_bound = i16::MAX;
+        for (step, neighbour) in neighbours(search.walls, state, arrival, search.limits, search.goal, &mut search.stats) {
+            search.stats.nodes_generated += 1;
+            let key = search.goal.visited_key(neighbour, search.limits.effective_visited_key());
+            if search.path_states.contains(&key) {
+                search.stats.duplicates_skipped += 1;
+                continue;
+            }
+            search.path.push(step);
+            search.path_states.push(key);
+            let neighbour_arrival = Arrival { step, from: state.robot(step.colour) };
+            match self.deepen(search, neighbour, Some(neighbour_arrival), distance + 1) {
+                Deepening::Found => return Deepening::Found,
+                Deepening::Stopped(stopped) => return Deepening::Stopped(stopped),
+                Deepening::Exceeded(estimate) => next_bound = next_bound.min(estimate),
+            }
+            search.path.pop();
+            search.path_states.pop();
+        }
+        Deepening::Exceeded(next_bound)
+    }
+}
+
+impl<'a> Solver for IdaStar<'a> {
+    fn heuristic(&self) -> Option<&dyn Heuristic> {
+        Some(self.heuristic)
+    }
+
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult {
+        let start = start_state(board, goal, limits);
+        let walls = Walls::new(&board.empty_board());
+        let mut search = Deepen {
+            walls: &walls,
+            target: board.get_goal(),
+            goal,
+            limits,
+            bound: goal.estimate(self.heuristic, start),
+            path: Vec::new(),
+            path_states: vec![goal.visited_key(start, limits.effective_visited_key())],
+            stats: SolveStats::default(),
+            depth_cut: false,
+            watch: Watch::new(limits),
+        };
+        loop {
+            match self.deepen(&mut search, start, None, 0) {
+                Deepening::Found => return SolveResult::solved(search.path, search.stats),
+                Deepening::Stopped(stopped) => return SolveResult::unsolved(Some(stopped), search.stats),
+                Deepening::Exceeded(i16::MAX) => {
+                    let stopped = if search.depth_cut { Some(StopReason::DepthLimit) } else { None };
+                    return SolveResult::unsolved(stopped, search.stats);
+                },
+                Deepening::Exceeded(next_bound) => search.bound = next_bound,
+            }
+        }
+    }
+}
+
+// Breadth first, keeping only the `width` boards with the best estimates at
+// each depth. Fast, but the solution can be longer than optimal, or missed.
+pub struct Beam<'a> {
+    heuristic: &'a dyn Heuristic,
+    width: usize,
+}
+
+impl<'a> Beam<'a> {
+    pub fn new(heuristic: &'a dyn Heuristic, width: usize) -> Beam<'a> {
+        Beam { heuristic, width }
+    }
+}
+
+impl<'a> Solver for Beam<'a> {
+    fn heuristic(&self) -> Option<&dyn Heuristic> {
+        Some(self.heuristic)
+    }
+
+    fn search(&self, board: &Board, goal: &GoalSpec, limits: &Limits) -> SolveResult {
+        let mut stats = SolveStats::default();
+        let walls = Walls::new(&board.empty_board());
+        let target = board.get_goal();
+        let start = start_state(board, goal, limits);
+        if goal.is_solved(target, start) {
+            return SolveResult::solved(Vec::new(), stats);
+        }
+        let mut trail = Trail::new();
+        let mut visited_board_states = HashSet::new();
+        let visited_key = limits.effective_visited_key();
+        visited_board_states.insert(goal.visited_key(start, visited_key));
+        let mut frontier = vec![(start, ROOT, None)];
+        let mut distance = 0;
+        let mut watch = Watch::new(limits);
+
+        while !frontier.is_empty() {
+            if !limits.allows_depth(distance + 1) {
+                return SolveResult::unsolved(Some(StopReason::DepthLimit), stats);
+            }
+            let mut candidates = Vec::new();
+            for (current, path, arrival) in frontier {
+                if let Some(stopped) = watch.check(&stats, distance) {
+                    return SolveResult::unsolved(Some(stopped), stats);
+                }
+                stats.nodes_expanded += 1;
+                for (step, neighbour) in neighbours(&walls, current, arrival, limits, goal, &mut stats) {
+                    stats.nodes_generated += 1;
+                    if !visited_board_states.insert(goal.visited_key(neighbour, visited_key)) {
+                        stats.duplicates_skipped += 1;
+                        continue;
+                    }
+                    let neighbour_path = trail.push(path, step);
+                    if goal.is_solved(target, neighbour) {
+                        return SolveResult::solved(trail.moves(neighbour_path), stats);
+                    }
+                    let estimate = goal.estimate(self.heuristic, neighbour);
+                    stats.heuristic_calls += 1;
+                    let neighbour_arrival = Arrival { step, from: current.robot(step.colour) };
+                    candidates.push((estimate, neighbour, neighbour_path, Some(neighbour_arrival)));
+                }
+            }
+            stats.peak_open = stats.peak_open.max(candidates.len() as u64);
+            candidates.sort_by_key(|(estimate, _, _, _)| *estimate);
+            candidates.truncate(self.width);
+            frontier = candidates.into_iter().map(|(_, state, path, arrival)| (state, path, arrival)).collect();
+            distance += 1;
+        }
+        SolveResult::unsolved(Some(StopReason::Incomplete), stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::board::EmptyBoard;
+    use crate::board::load_map;
+    use crate::distance::distance_map;
+    use crate::together::Together;
+    use crate::together::TogetherDistances;
+
+    // Red starts on its own target, so the fewest robots and target moves
+    // leave it where it is.
+    #[test]
+    fn tie_break_leaves_a_lead_robot_that_is_home() {
+        let map = Rc::new(load_map("maps/map1.txt"));
+        let board = Board::with_map(
+            map.clone(),
+            Position { x: 5, y: 9 },
+            Position { x: 10, y: 5 },
+            Position { x: 15, y: 3 },
+            Position { x: 3, y: 8 },
+            Position { x: 6, y: 14 },
+        );
+        let together = Together::new(&[(Colour::RED, Position { x: 5, y: 9 }), (Colour::GREEN, Position { x: 14, y: 3 })]).unwrap();
+        let distances = together.targets().iter()
+            .map(|(_, target)| Rc::new(distance_map(&EmptyBoard::with_map(*map, *target), *target)))
+            .collect();
+        let heuristic = TogetherDistances::new(&together, distances);
+        let goal = GoalSpec::Together(together);
+        let limits = Limits { tie_break: TieBreak::FewestRobots, ..Limits::default() };
+        let solution = AStar::new(&heuristic).solve(&board, &goal, &limits).solution.expect("solvable");
+        assert!(solution.verify(&board, &goal, &limits.rules));
+        assert_eq!(solution.length(), 6);
+        assert_eq!(solution.robots_moved(), 2);
+        assert_eq!(solution.moves_of(Colour::RED), 0);
+    }
+}
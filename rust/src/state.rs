@@ -0,0 +1,87 @@
+use crate::board::Colour;
+use crate::board::Position;
+
+// Where the robots stand, packed the same way as `Board::hash`: a byte per
+// robot in the order red, green, blue, yellow, x in the low four bits. The
+// search works on these against a map it shares, and only builds a `Board`
+// for its result. The top 28 bits are progress the goal, rules and
+// constraints keep track of along the way, so boards with the same robots but
+// different progress are different states. The bits between are left for
+// searches to tag keys with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct State(u64);
+
+impl State {
+    pub fn new(red: Position, green: Position, blue: Position, yellow: Position) -> State {
+        State(pack(red) | pack(green) << 8 | pack(blue) << 16 | pack(yellow) << 24)
+    }
+
+    pub fn key(self) -> u64 {
+        self.0
+    }
+
+    pub fn robot(self, colour: Colour) -> Position {
+        unpack(self.0 >> shift(colour))
+    }
+
+    pub fn aux(self) -> u32 {
+        (self.0 >> AUX_SHIFT) as u32
+    }
+
+    pub fn with_aux(self, aux: u32) -> State {
+        State(self.0 & !(AUX_MASK << AUX_SHIFT) | (aux as u64 & AUX_MASK) << AUX_SHIFT)
+    }
+
+    pub fn robots(self) -> [Position; 4] {
+        [unpack(self.0), unpack(self.0 >> 8), unpack(self.0 >> 16), unpack(self.0 >> 24)]
+    }
+
+    pub fn with_robot(self, colour: Colour, position: Position) -> State {
+        let shift = shift(colour);
+        State(self.0 & !(0xff << shift) | pack(position) << shift)
+    }
+
+    // The same placement and progress with the robots other than `target`
+    // sorted by tile, so every way of colouring the helpers gets one key.
+    pub fn canonical(self, target: Colour) -> State {
+        let target_shift = shift(target);
+        let mut helpers = [0u64; 3];
+        let mut count = 0;
+        for robot_shift in [0, 8, 16, 24].iter() {
+            if *robot_shift != target_shift {
+                helpers[count] = self.0 >> robot_shift & 0xff;
+                count += 1;
+            }
+        }
+        helpers.sort_unstable();
+        let mut key = self.0 & (0xff << target_shift | AUX_MASK << AUX_SHIFT);
+        let mut next = 0;
+        for robot_shift in [0, 8, 16, 24].iter() {
+            if *robot_shift != target_shift {
+                key |= helpers[next] << robot_shift;
+                next += 1;
+            }
+        }
+        State(key)
+    }
+}
+
+const AUX_SHIFT: u32 = 36;
+const AUX_MASK: u64 = 0xfff_ffff;
+
+fn shift(colour: Colour) -> u32 {
+    match colour {
+        Colour::RED => 0,
+        Colour::GREEN => 8,
+        Colour::BLUE => 16,
+        Colour::YELLOW => 24,
+    }
+}
+
+fn pack(position: Position) -> u64 {
+    position.x as u64 | (position.y as u64) << 4
+}
+
+fn unpack(bits: u64) -> Position {
+    Position { x: (bits & 0xf) as i8, y: (bits >> 4 & 0xf) as i8 }
+}
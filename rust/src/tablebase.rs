@@ -0,0 +1,275 @@
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::board::Colour;
+use crate::board::Position;
+use crate::colour_to_int;
+use crate::to_colour;
+use crate::transposition::DistanceTable;
+
+// On-disk layout, integers are little endian:
+//
+//    0  magic "RRTB"
+//    4  format version
+//    5  target colour
+//    6  robot count, target included
+//    7  bits per entry, 4 or 8
+//    8  map hash
+//   16  goal x
+//   17  goal y
+//   18  padding
+//   24  entry count
+//   32  entries
+//
+// Entry `i` holds the optimal solve length of the placement with index `i`
+// (see `placement_index`), or the all-ones value if that placement was never
+// solved.
+const MAGIC: &[u8; 4] = b"RRTB";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 32;
+const TILES: u64 = 256;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Header {
+    pub map_hash: u64,
+    pub goal: Position,
+    pub colour: Colour,
+    pub robot_count: u8,
+    pub entry_bits: u8,
+}
+
+impl Header {
+    pub fn new(map_hash: u64, goal: Position, colour: Colour, entry_bits: u8) -> Header {
+        Header {
+            map_hash,
+            goal,
+            colour,
+            robot_count: 4,
+            entry_bits,
+        }
+    }
+
+    pub fn entry_count(&self) -> u64 {
+        TILES * binomial(TILES - 1, self.robot_count as u64 - 1)
+    }
+
+    fn unknown(&self) -> u8 {
+        if self.entry_bits == 4 { 0x0F } else { 0xFF }
+    }
+
+    fn data_len(&self) -> usize {
+        let count = self.entry_count() as usize;
+        if self.entry_bits == 4 { count.div_ceil(2) } else { count }
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = colour_to_int(self.colour) as u8;
+        bytes[6] = self.robot_count;
+        bytes[7] = self.entry_bits;
+        bytes[8..16].copy_from_slice(&self.map_hash.to_le_bytes());
+        bytes[16] = self.goal.x as u8;
+        bytes[17] = self.goal.y as u8;
+        bytes[24..32].copy_from_slice(&self.entry_count().to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Header> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err(invalid("not a tablebase file"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid("unsupported tablebase version"));
+        }
+        if bytes[5] > 3 || bytes[6] < 1 || bytes[6] > 4 || (bytes[7] != 4 && bytes[7] != 8) || bytes[16] > 15 || bytes[17] > 15 {
+            return Err(invalid("corrupt tablebase header"));
+        }
+        let mut map_hash = [0; 8];
+        map_hash.copy_from_slice(&bytes[8..16]);
+        let mut entry_count = [0; 8];
+        entry_count.copy_from_slice(&bytes[24..32]);
+
+        let header = Header {
+            map_hash: u64::from_le_bytes(map_hash),
+            goal: Position { x: bytes[16] as i8, y: bytes[17] as i8 },
+            colour: to_colour(bytes[5] as u64),
+            robot_count: bytes[6],
+            entry_bits: bytes[7],
+        };
+        if u64::from_le_bytes(entry_count) != header.entry_count() {
+            return Err(invalid("tablebase entry count doesn't match its robot count"));
+        }
+        Ok(header)
+    }
+}
+
+pub struct Tablebase {
+    header: Header,
+    mmap: Mmap,
+}
+
+impl Tablebase {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Tablebase> {
+        let file = File::open(path)?;
+        // The file is never written while mapped, writers replace it whole.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let header = Header::from_bytes(&mmap)?;
+        if mmap.len() < HEADER_SIZE + header.data_len() {
+            return Err(invalid("tablebase file is truncated"));
+        }
+        Ok(Tablebase { header, mmap })
+    }
+
+    // Whether this table answers solves of `colour` to `goal` on the map
+    // with `map_hash`.
+    pub fn matches(&self, map_hash: u64, goal: Position, colour: Colour) -> bool {
+        self.header.map_hash == map_hash
+            && self.header.goal == goal
+            && colour_to_int(self.header.colour) == colour_to_int(colour)
+    }
+
+    // Robots in `Board::get_robots` order.
+    pub fn get(&self, robots: &[Position]) -> Option<i16> {
+        let (target, helpers) = split_robots(robots, self.header.colour);
+        if helpers.len() + 1 != self.header.robot_count as usize {
+            return None;
+        }
+        let index = placement_index(target, &helpers)?;
+        let value = read_entry(&self.mmap[HEADER_SIZE..], self.header.entry_bits, index);
+        if value == self.header.unknown() {
+            return None;
+        }
+        Some(value as i16)
+    }
+
+    // Builds a table from solve lengths keyed by `Board::hash`, on top of the
+    // entries of `base` if it has the same layout. Lengths that don't fit in
+    // an entry are left unknown. Returns how many entries were filled in.
+    pub fn write<P: AsRef<Path>>(path: P, header: Header, known_solutions: &dyn DistanceTable, base: Option<&Tablebase>) -> io::Result<u64> {
+        let unknown = header.unknown();
+        let mut data = match base {
+            Some(base) if base.header.to_bytes() == header.to_bytes() => {
+                base.mmap[HEADER_SIZE..HEADER_SIZE + header.data_len()].to_vec()
+            },
+            _ => {
+                let fill = if header.entry_bits == 4 { 0xFF } else { unknown };
+                vec![fill; header.data_len()]
+            },
+        };
+        let mut written = 0;
+        for (hash, length) in known_solutions.entries() {
+            if length < 0 || length >= unknown as i16 {
+                continue;
+            }
+            let (target, helpers) = split_robots(&unhash(hash), header.colour);
+            if let Some(index) = placement_index(target, &helpers) {
+                write_entry(&mut data, header.entry_bits, index, length as u8);
+                written += 1;
+            }
+        }
+
+        // Write next to the destination and rename, so a mapped reader of
+        // the old file never sees a half written one.
+        let path = path.as_ref();
+        let temp_path = path.with_extension("partial");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(&header.to_bytes())?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(written)
+    }
+}
+
+// Perfect index over placements: the target's tile, times the colex rank of
+// the helper set among the 255 other tiles. Helper colours are not part of
+// the index, so every permutation of the helpers shares one entry. Returns
+// None when two robots share a tile.
+pub fn placement_index(target: Position, helpers: &[Position]) -> Option<u64> {
+    let target_tile = tile_index(target);
+    let mut others: Vec<u64> = Vec::with_capacity(helpers.len());
+    for helper in helpers {
+        let tile = tile_index(*helper);
+        if tile == target_tile {
+            return None;
+        }
+        others.push(if tile > target_tile { tile - 1 } else { tile });
+    }
+    others.sort_unstable();
+    let mut rank = 0;
+    for (i, tile) in others.iter().enumerate() {
+        if i > 0 && others[i - 1] == *tile {
+            return None;
+        }
+        rank += binomial(*tile, i as u64 + 1);
+    }
+    Some(target_tile * binomial(TILES - 1, helpers.len() as u64) + rank)
+}
+
+fn tile_index(position: Position) -> u64 {
+    position.x as u64 + 16 * position.y as u64
+}
+
+fn split_robots(robots: &[Position], colour: Colour) -> (Position, Vec<Position>) {
+    // `Board::get_robots` order, which is also the `Board::hash` order.
+    let target = match colour {
+        Colour::RED => 0,
+        Colour::GREEN => 1,
+        Colour::BLUE => 2,
+        Colour::YELLOW => 3,
+    };
+    let helpers = robots.iter().enumerate().filter(|(i, _)| *i != target).map(|(_, robot)| *robot).collect();
+    (robots[target], helpers)
+}
+
+fn unhash(hash: u64) -> [Position; 4] {
+    let mut robots = [Position { x: 0, y: 0 }; 4];
+    for (i, robot) in robots.iter_mut().enumerate() {
+        robot.x = ((hash >> (8 * i)) & 0xF) as i8;
+        robot.y = ((hash >> (8 * i + 4)) & 0xF) as i8;
+    }
+    robots
+}
+
+fn read_entry(data: &[u8], entry_bits: u8, index: u64) -> u8 {
+    if entry_bits == 4 {
+        let byte = data[(index / 2) as usize];
+        if index & 1 == 0 { byte & 0x0F } else { byte >> 4 }
+    } else {
+        data[index as usize]
+    }
+}
+
+fn write_entry(data: &mut [u8], entry_bits: u8, index: u64, value: u8) {
+    if entry_bits == 4 {
+        let byte = &mut data[(index / 2) as usize];
+        if index & 1 == 0 {
+            *byte = (*byte & 0xF0) | value;
+        } else {
+            *byte = (*byte & 0x0F) | (value << 4);
+        }
+    } else {
+        data[index as usize] = value;
+    }
+}
+
+pub fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let mut result = 1;
+    for i in 0..k {
+        result = result * (n - i) / (i + 1);
+    }
+    result
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,96 @@
+use std::rc::Rc;
+
+use crate::board::COLOURS;
+use crate::board::Colour;
+use crate::board::Position;
+use crate::colour_to_int;
+use crate::distance::DistanceMap;
+use crate::heuristic::Heuristic;
+use crate::state::State;
+
+// Robots that each have a target of their own, all of which have to stand
+// on them at once, no two on the same tile. The board's goal plays no part.
+// Rules and constraints take the first of them in colour order as the
+// target robot, and its target as the goal.
+#[derive(Debug, Copy, Clone)]
+pub struct Together {
+    // Each robot's target, in colour order.
+    targets: [Option<Position>; 4],
+}
+
+impl Together {
+    pub fn new(targets: &[(Colour, Position)]) -> Result<Together, String> {
+        if targets.is_empty() {
+            return Err("no robots to place".to_string());
+        }
+        let mut together = Together { targets: [None; 4] };
+        for (colour, position) in targets {
+            let robot = colour_to_int(*colour) as usize;
+            if together.targets[robot].is_some() {
+                return Err(format!("{:?} has more than one target", colour));
+            }
+            if together.targets.contains(&Some(*position)) {
+                return Err(format!("more than one robot has to stand on {}", position));
+            }
+            together.targets[robot] = Some(*position);
+        }
+        Ok(together)
+    }
+
+    // Each robot with a target, and where it is.
+    pub fn targets(&self) -> Vec<(Colour, Position)> {
+        COLOURS.iter().zip(self.targets.iter())
+            .filter_map(|(colour, target)| target.map(|position| (*colour, position)))
+            .collect()
+    }
+
+    pub fn count(&self) -> usize {
+        self.targets.iter().filter(|target| target.is_some()).count()
+    }
+
+    pub fn lead_colour(&self) -> Colour {
+        let robot = self.targets.iter().position(|target| target.is_some()).expect("at least one target");
+        COLOURS[robot]
+    }
+
+    pub fn lead_target(&self) -> Position {
+        self.targets.iter().find_map(|target| *target).expect("at least one target")
+    }
+
+    pub fn is_placed(&self, state: State) -> bool {
+        COLOURS.iter().zip(self.targets.iter())
+            .all(|(colour, target)| target.is_none_or(|position| state.robot(*colour) == position))
+    }
+}
+
+// Each robot's moves to its own target on its own, added up: a move only
+// moves one robot, so no move counts towards two of them.
+pub struct TogetherDistances {
+    // The map towards each robot's target, in colour order.
+    distances: [Option<Rc<DistanceMap>>; 4],
+}
+
+impl TogetherDistances {
+    // `distances` has the distance map towards each target of `together`,
+    // in its order.
+    pub fn new(together: &Together, distances: Vec<Rc<DistanceMap>>) -> TogetherDistances {
+        let targets = together.targets();
+        assert_eq!(distances.len(), targets.len(), "a distance map for each target");
+        let mut by_colour = [None, None, None, None];
+        for ((colour, _), map) in targets.iter().zip(distances) {
+            by_colour[colour_to_int(*colour) as usize] = Some(map);
+        }
+        TogetherDistances { distances: by_colour }
+    }
+}
+
+impl Heuristic for TogetherDistances {
+    fn evaluate(&self, state: State, _target_colour: Colour) -> i16 {
+        COLOURS.iter().zip(self.distances.iter())
+            .filter_map(|(colour, distances)| distances.as_ref().map(|distances| {
+                let robot = state.robot(*colour);
+                distances[robot.x as usize][robot.y as usize] as i16
+            }))
+            .sum()
+    }
+}
@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use crate::board::Colour;
+use crate::board::Position;
+use crate::colour_to_int;
+use crate::distance::DistanceMap;
+use crate::heuristic::Heuristic;
+use crate::state::State;
+
+// Bits of a state's aux progress a tour uses, above the constraints': how
+// many checkpoints the target robot has stopped on so far.
+const PROGRESS_SHIFT: u32 = 21;
+const PROGRESS_BITS: u32 = 3;
+
+pub const MAX_CHECKPOINTS: usize = (1 << PROGRESS_BITS) - 1;
+
+// Tiles the target robot has to stop on in order before it finishes on the
+// board's goal. Stopping on one out of turn doesn't count, nor does starting
+// on the first.
+#[derive(Debug, Copy, Clone)]
+pub struct Tour {
+    pub colour: Colour,
+    checkpoints: [Position; MAX_CHECKPOINTS],
+    count: usize,
+}
+
+impl Tour {
+    pub fn new(colour: Colour, checkpoints: &[Position]) -> Result<Tour, String> {
+        if checkpoints.len() > MAX_CHECKPOINTS {
+            return Err(format!("a tour can have at most {} checkpoints, not {}", MAX_CHECKPOINTS, checkpoints.len()));
+        }
+        let mut tour = Tour { colour, checkpoints: [Position { x: 0, y: 0 }; MAX_CHECKPOINTS], count: checkpoints.len() };
+        tour.checkpoints[..checkpoints.len()].copy_from_slice(checkpoints);
+        Ok(tour)
+    }
+
+    pub fn checkpoints(&self) -> &[Position] {
+        &self.checkpoints[..self.count]
+    }
+
+    // How many checkpoints `state` has been through.
+    pub fn progress(&self, state: State) -> usize {
+        (state.aux() >> PROGRESS_SHIFT & MAX_CHECKPOINTS as u32) as usize
+    }
+
+    pub fn is_complete(&self, state: State) -> bool {
+        self.progress(state) == self.count
+    }
+
+    // `after`, the board once the robot of `colour` has moved, with the
+    // checkpoint it stopped on counted if it was the next one.
+    pub fn advance(&self, after: State, colour: Colour) -> State {
+        let progress = self.progress(after);
+        if colour_to_int(colour) != colour_to_int(self.colour) || progress == self.count || after.robot(colour) != self.checkpoints[progress] {
+            return after;
+        }
+        after.with_aux(after.aux() + (1 << PROGRESS_SHIFT))
+    }
+}
+
+// The target robot's moves to the next checkpoint on its own, plus those of
+// each leg after it from one checkpoint to the next and on to the goal. Every
+// leg is a flood fill bound on moves of the target robot alone, and the legs
+// don't share any, so the sum is a bound too.
+pub struct TourDistances {
+    tour: Tour,
+    // The map towards each checkpoint, then towards the goal.
+    legs: Vec<Rc<DistanceMap>>,
+    // What the legs after the one towards each stop add up to.
+    remaining: Vec<i16>,
+}
+
+impl TourDistances {
+    // `legs` has the distance map of every checkpoint of `tour` in order,
+    // then that of the goal.
+    pub fn new(tour: Tour, legs: Vec<Rc<DistanceMap>>) -> TourDistances {
+        assert_eq!(legs.len(), tour.count + 1, "a distance map for each checkpoint and one for the goal");
+        let mut remaining = vec![0; legs.len()];
+        for leg in (0..tour.count).rev() {
+            let from = tour.checkpoints[leg];
+            remaining[leg] = remaining[leg + 1] + legs[leg + 1][from.x as usize][from.y as usize] as i16;
+        }
+        TourDistances { tour, legs, remaining }
+    }
+}
+
+impl Heuristic for TourDistances {
+    fn evaluate(&self, state: State, target_colour: Colour) -> i16 {
+        let leg = self.tour.progress(state);
+        let target = state.robot(target_colour);
+        self.legs[leg][target.x as usize][target.y as usize] as i16 + self.remaining[leg]
+    }
+}
@@ -0,0 +1,177 @@
+use std::cell::Cell;
+use std::mem::size_of;
+
+use hashbrown::HashMap;
+
+// Distances by state key, as kept by a search's visited set or by the cache
+// of known solutions across solves.
+pub trait DistanceTable {
+    fn get(&self, key: u64) -> Option<i16>;
+    fn insert(&mut self, key: u64, distance: i16);
+    fn entries(&self) -> Box<dyn Iterator<Item = (u64, i16)> + '_>;
+}
+
+// Keeps everything, however much memory that takes.
+impl DistanceTable for HashMap<u64, i16> {
+    fn get(&self, key: u64) -> Option<i16> {
+        HashMap::get(self, &key).copied()
+    }
+
+    fn insert(&mut self, key: u64, distance: i16) {
+        HashMap::insert(self, key, distance);
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = (u64, i16)> + '_> {
+        Box::new(self.iter().map(|(key, distance)| (*key, *distance)))
+    }
+}
+
+// What a full transposition table does with a new entry.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Replacement {
+    // Replace the entry with the smallest depth, unless the new one's is
+    // smaller still. For solve lengths that keeps the ones that cost the
+    // most to find.
+    #[default]
+    DepthPreferred,
+    // Replace the entry in the key's home slot.
+    AlwaysReplace,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TableStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+    // Entries for one key overwritten by another.
+    pub evictions: u64,
+    // New entries dropped to keep deeper ones.
+    pub rejections: u64,
+}
+
+impl TableStats {
+    pub fn add(&mut self, other: &TableStats) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.stores += other.stores;
+        self.evictions += other.evictions;
+        self.rejections += other.rejections;
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Entry {
+    key: u64,
+    distance: i16,
+    depth: i16,
+}
+
+const EMPTY: u64 = u64::MAX;
+
+// Slots looked at for each key, from its home slot on.
+const PROBES: usize = 4;
+
+// A fixed number of slots, sized from a byte budget, with open addressing
+// over a short probe window. Keys are stored in full, so a lookup never
+// answers for the wrong board; it can only forget.
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+    shift: u32,
+    replacement: Replacement,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+    stores: u64,
+    evictions: u64,
+    rejections: u64,
+}
+
+impl TranspositionTable {
+    pub fn new(byte_budget: usize, replacement: Replacement) -> TranspositionTable {
+        let mut slots = 1usize;
+        while slots * 2 * size_of::<Entry>() <= byte_budget {
+            slots *= 2;
+        }
+        TranspositionTable {
+            entries: vec![Entry { key: EMPTY, distance: 0, depth: 0 }; slots],
+            shift: 64 - slots.trailing_zeros(),
+            replacement,
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+            stores: 0,
+            evictions: 0,
+            rejections: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn stats(&self) -> TableStats {
+        TableStats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+            stores: self.stores,
+            evictions: self.evictions,
+            rejections: self.rejections,
+        }
+    }
+
+    pub fn insert_with_depth(&mut self, key: u64, distance: i16, depth: i16) {
+        let home = self.home(key);
+        let mut victim = home;
+        for probe in 0..PROBES.min(self.entries.len()) {
+            let slot = (home + probe) & (self.entries.len() - 1);
+            let entry = self.entries[slot];
+            if entry.key == key || entry.key == EMPTY {
+                self.entries[slot] = Entry { key, distance, depth };
+                self.stores += 1;
+                return;
+            }
+            if self.replacement == Replacement::DepthPreferred && entry.depth < self.entries[victim].depth {
+                victim = slot;
+            }
+        }
+        if self.replacement == Replacement::DepthPreferred && depth < self.entries[victim].depth {
+            self.rejections += 1;
+            return;
+        }
+        self.entries[victim] = Entry { key, distance, depth };
+        self.stores += 1;
+        self.evictions += 1;
+    }
+
+    fn home(&self, key: u64) -> usize {
+        if self.shift == 64 {
+            return 0;
+        }
+        (key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> self.shift) as usize
+    }
+}
+
+impl DistanceTable for TranspositionTable {
+    fn get(&self, key: u64) -> Option<i16> {
+        let home = self.home(key);
+        for probe in 0..PROBES.min(self.entries.len()) {
+            let entry = self.entries[(home + probe) & (self.entries.len() - 1)];
+            if entry.key == key {
+                self.hits.set(self.hits.get() + 1);
+                return Some(entry.distance);
+            }
+            if entry.key == EMPTY {
+                break;
+            }
+        }
+        self.misses.set(self.misses.get() + 1);
+        None
+    }
+
+    // The distance is the depth too.
+    fn insert(&mut self, key: u64, distance: i16) {
+        self.insert_with_depth(key, distance, distance);
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = (u64, i16)> + '_> {
+        Box::new(self.entries.iter().filter(|entry| entry.key != EMPTY).map(|entry| (entry.key, entry.distance)))
+    }
+}